@@ -0,0 +1,32 @@
+use rustmiko::devices::cisco::AsyncCiscoTelnet;
+use rustmiko::devices::generic::connection::ConnectionBuilder;
+use rustmiko::devices::generic::device_types::async_config::AsyncConfigurable;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	env_logger::init();
+
+	let switches = ["192.168.1.101", "192.168.1.102", "192.168.1.103"];
+
+	let mut tasks = Vec::new();
+	for address in switches {
+		tasks.push(tokio::spawn(async move {
+			let builder = ConnectionBuilder::new(address)
+				.username("admin")
+				.password("admin");
+
+			let mut cisco = AsyncCiscoTelnet::connect_with_async(&builder).await?;
+			let version = cisco.send_command("show version").await?;
+			Ok::<_, rustmiko::Error>((address, version))
+		}));
+	}
+
+	for task in tasks {
+		match task.await? {
+			Ok((address, version)) => println!("{address}:\n{version}"),
+			Err(e) => eprintln!("Failed to talk to the switch: {e}"),
+		}
+	}
+
+	Ok(())
+}
@@ -15,16 +15,15 @@ fn main() -> anyhow::Result<()> {
 		},
 	};
 
-	{
-		let mut config = cisco.enter_config()?;
-		for index in 1..=8 {
-			let interface = config.get_interface("gi", &[0, index]);
-			match config.interface_up(&interface) {
-			    Ok(_) => println!("Interface {} is now up", interface.name()),
-			    Err(_) => println!("Failed to set Interface {} up", interface.name())
-			}
+	let mut config = cisco.enter_config()?;
+	for index in 1..=8 {
+		let interface = config.get_interface("gi", &[0, index]);
+		match config.interface_up(&interface) {
+		    Ok(_) => println!("Interface {} is now up", interface.name()),
+		    Err(_) => println!("Failed to set Interface {} up", interface.name())
 		}
 	}
+	config.finish()?;
 
 	if let Err(e) = cisco.save() {
 		eprintln!("Failed to save configuration: {e}");
@@ -5,5 +5,6 @@ fn main() {
     let mut device = TelnetConnection::connect("192.168.1.101:23", Some("admin"), Some("password")).unwrap();
     let prompt_end = Regex::new(r"[#>]").unwrap();
 
-    device.execute_raw("show running configuration", &prompt_end).unwrap();
-}
\ No newline at end of file
+    let output = device.send_command("show running configuration", &prompt_end).unwrap();
+    println!("{output}");
+}
@@ -6,6 +6,7 @@ use rustmiko::devices::juniper::JuniperSSH;
 fn main() -> anyhow::Result<()> {
     env_logger::init();
 
+    // The host key is added to ~/.ssh/known_hosts on the first connection and checked afterwards.
     let mut juniper = match JuniperSSH::connect("192.168.178.1:22", "admin", "admin") {
         Ok(device) => {
             println!("Connected successfully");
@@ -18,20 +19,20 @@ fn main() -> anyhow::Result<()> {
     };
 
     juniper.enter_cli()?;
-    {
-        let mut config = juniper.enter_config()?;
-        let interface = config.get_interface("ge-", &[0, 0, 0]);
+    let mut config = juniper.enter_config()?;
+    let interface = config.get_interface("ge-", &[0, 0, 0]);
 
-        config.interface_down(&interface)?;
+    config.interface_down(&interface)?;
 
-        config.commit()?;
+    config.commit()?;
 
-        sleep(Duration::from_secs(20));
+    sleep(Duration::from_secs(20));
 
-        config.interface_up(&interface)?;
+    config.interface_up(&interface)?;
 
-        config.commit()?;
-    }
+    config.commit()?;
+
+    config.finish()?;
 
     Ok(())
 }
\ No newline at end of file
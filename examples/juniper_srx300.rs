@@ -1,12 +1,20 @@
 use std::thread::sleep;
 use std::time::Duration;
+use rustmiko::devices::generic::connection::{ConnectionBuilder, HostKeyPolicy};
 use rustmiko::devices::generic::device_types::config::{Configurable, InterfaceConfigurable};
 use rustmiko::devices::juniper::JuniperSSH;
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
 
-    let mut juniper = match JuniperSSH::connect("192.168.1.102", "admin", "password") {
+    let builder = ConnectionBuilder::new("192.168.1.102")
+        .username("admin")
+        .password("password")
+        // Only connect if the firewall is in ~/.ssh/known_hosts already.
+        .host_key_policy(HostKeyPolicy::Strict)
+        .command_timeout(Duration::from_secs(120));
+
+    let mut juniper = match JuniperSSH::connect_with(&builder) {
         Ok(device) => {
             println!("Connected successfully");
             device
@@ -18,20 +26,20 @@ fn main() -> anyhow::Result<()> {
     };
 
     juniper.enter_cli()?;
-    {
-        let mut config = juniper.enter_config()?;
-        let interface = config.get_interface("ge-", &[0, 0, 5]);
+    let mut config = juniper.enter_config()?;
+    let interface = config.get_interface("ge-", &[0, 0, 5]);
+
+    config.interface_down(&interface)?;
 
-        config.interface_down(&interface)?;
+    config.commit()?;
 
-        config.commit()?;
+    sleep(Duration::from_secs(20));
 
-        sleep(Duration::from_secs(20));
+    config.interface_up(&interface)?;
 
-        config.interface_up(&interface)?;
+    config.commit()?;
 
-        config.commit()?;
-    }
+    config.finish()?;
 
     Ok(())
 }
\ No newline at end of file
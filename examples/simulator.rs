@@ -0,0 +1,17 @@
+use std::io::stdin;
+use rustmiko::simulator::Simulator;
+
+fn main() -> anyhow::Result<()> {
+	env_logger::init();
+
+	let cisco = Simulator::cisco("sw01").enable_secret("secret").start()?;
+	let juniper = Simulator::junos("srx").start()?;
+
+	println!("Cisco IOS  telnet {}  ssh {}", cisco.telnet_addr(), cisco.ssh_addr());
+	println!("Junos      telnet {}  ssh {}", juniper.telnet_addr(), juniper.ssh_addr());
+	println!("Host key   {}", cisco.host_key_fingerprint());
+	println!("Log in as admin/admin, the enable secret is \"secret\". Press enter to stop.");
+
+	stdin().read_line(&mut String::new())?;
+	Ok(())
+}
@@ -1,10 +1,36 @@
-use std::error::Error;
-use std::io;
 use std::net::ToSocketAddrs;
+#[cfg(feature = "async")]
+use async_trait::async_trait;
 use regex::Regex;
-use crate::devices::generic::connection::Connection;
-use crate::devices::generic::device_types::config::{Configurable, ConfigurationMode, InterfaceConfigurable};
+#[cfg(feature = "async")]
+use crate::devices::generic::connection::AsyncConnection;
+use crate::devices::cisco::CiscoMode;
+use crate::devices::generic::connection::{last_line, Connection, ConnectionBuilder};
+#[cfg(feature = "async")]
+use crate::devices::generic::device_types::async_config::{AsyncConfigurable, AsyncConfigurationMode, AsyncInterfaceConfigurable, AsyncSubMode};
+use crate::devices::generic::device_types::config::{Configurable, ConfigurationMode, InterfaceConfigurable, InterfaceMode, LineMode, RouterMode, SubMode, VlanMode};
 use crate::devices::generic::device_types::interfaces::Interface;
+use crate::devices::generic::error_patterns::ErrorPatterns;
+use crate::error::{Error, Result};
+
+/// How often IOS asks for the enable secret before giving up.
+const ENABLE_ATTEMPTS: usize = 3;
+
+/// Output that marks a command as rejected by IOS.
+const ERROR_PATTERNS: &[&str] = &[
+    r"^% Invalid input detected",
+    r"^% Incomplete command",
+    r"^% Ambiguous command",
+    r"^% Unknown command",
+    r"^% Bad secrets",
+    r"^%\s*Error",
+];
+
+/// Output of a successful `configure replace` or `configure revert now`.
+const ROLLBACK_DONE: &str = "Rollback Done";
+
+/// Any prompt of an IOS device, whatever its hostname.
+const ANY_PROMPT: &str = r"^[^\s>#()]+(\([^)]*\))?[>#]$";
 
 /// A cisco (catalyst) device API implementation.
 ///
@@ -15,66 +41,984 @@ use crate::devices::generic::device_types::interfaces::Interface;
 ///
 /// [`CiscoSSH`]: crate::devices::cisco::CiscoSSH
 /// [`CiscoTelnet`]: crate::devices::cisco::CiscoTelnet
-pub struct CiscoDevice<C: Connection> {
+pub struct CiscoDevice<C> {
     connection: C,
+    base_prompt: String,
     prompt_end: Regex,
+    error_patterns: ErrorPatterns,
+    mode: CiscoMode,
+    /// The modes the current sub-mode was entered from, the closest one last, which `exit` goes
+    /// back to.
+    parent_modes: Vec<CiscoMode>,
 }
 
 impl<C: Connection<ConnectionHandler = C>> CiscoDevice<C> {
-    pub fn connect<A: ToSocketAddrs>(addr: A, username: &str, password: &str) -> Result<CiscoDevice<C>, Box<dyn Error>> {
+    pub fn connect<A: ToSocketAddrs>(addr: A, username: &str, password: &str) -> Result<CiscoDevice<C>> {
+        Self::from_connection(C::connect(addr, Some(username), Some(password))?)
+    }
+
+    /// Connect to the device with all the settings of a [`ConnectionBuilder`], like timeouts or a
+    /// custom port.
+    pub fn connect_with(builder: &ConnectionBuilder) -> Result<CiscoDevice<C>> {
+        Self::from_connection(C::connect_with(builder)?)
+    }
+
+    /// Enter privileged EXEC mode. The secret is only sent if the device asks for it, and the
+    /// device has to end up in privileged EXEC mode, otherwise a [`Error::WrongMode`] is returned.
+    /// Does nothing if the session already is privileged.
+    pub fn enable(&mut self, password: &str) -> Result<()> {
+        if self.mode != CiscoMode::UserExec {
+            return Ok(());
+        }
+
+        let password_prompt = Regex::new(r"[Pp]assword:?\s*$")?;
+        let prompt_end = Regex::new(&format!("{}|{}", password_prompt.as_str(), self.prompt_end.as_str()))?;
+
+        let line_ending = self.connection.line_ending().to_string();
+        self.connection.write_raw(format!("enable{}", line_ending).as_bytes())?;
+        let mut output = self.connection.read_until_prompt(&prompt_end)
+            .map_err(|e| e.with_command("enable"))?;
+
+        // IOS asks again after a wrong secret, those attempts are answered with empty lines until
+        // it gives up. The password is never passed on as the command, so it doesn't end up in
+        // any error.
+        let mut answer = password;
+        let mut attempts = 0;
+        while password_prompt.is_match(&output) && attempts < ENABLE_ATTEMPTS {
+            self.connection.write_raw(format!("{}{}", answer, line_ending).as_bytes())?;
+            output = self.connection.read_until_prompt(&prompt_end)
+                .map_err(|e| e.with_command("enable"))?;
+            answer = "";
+            attempts += 1;
+        }
+
+        self.update_mode(&output);
+        self.error_patterns.check("enable", output)?;
+        self.expect_mode("enable", &CiscoMode::PrivilegedExec)
+    }
+
+    /// Saves the running configuration. In configuration mode this is done with `do`.
+    pub fn save(&mut self) -> Result<()> {
+        let command = self.save_command();
+        self.execute_raw(command)
+    }
+}
+
+impl<C: Connection> CiscoDevice<C> {
+    /// Wrap an already established connection and discover the prompt of the device.
+    pub fn from_connection(mut connection: C) -> Result<CiscoDevice<C>> {
+        let prompt = connection.find_prompt(&Regex::new("[>#]")?)?;
+        let mut device = Self::new(connection, &prompt)?;
+        device.prepare_session()?;
+        Ok(device)
+    }
+
+    /// Prepares the session for automation by turning off the pager with `terminal length 0` and
+    /// widening the terminal with `terminal width 511`, so long output isn't interrupted and long
+    /// lines aren't wrapped. This runs automatically when connecting.
+    pub fn prepare_session(&mut self) -> Result<()> {
+        self.execute_raw("terminal length 0")?;
+        self.execute_raw("terminal width 511")
+    }
+
+    /// Discover the prompt again and take over the hostname and mode from it. `hostname` and
+    /// rollbacks sent through this device are noticed on their own, this is for changes it can't
+    /// see, e.g. from another session or a revert timer that ran out.
+    pub fn find_prompt(&mut self) -> Result<String> {
+        let prompt = self.connection.find_prompt(&Regex::new(ANY_PROMPT)?)?;
+        self.set_prompt(&prompt)?;
+        Ok(prompt)
+    }
+
+    /// Get the running configuration with `show running-config`, e.g. for a backup. The
+    /// `Building configuration...` header is removed, so only the configuration is returned.
+    /// Works in privileged EXEC and all configuration modes.
+    pub fn get_running_config(&mut self) -> Result<String> {
+        let command = self.show_config_command("show running-config")?;
+        let output = self.send_command(&command)?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Get the startup configuration with `show startup-config`, without the header about its
+    /// size. Works in privileged EXEC and all configuration modes.
+    pub fn get_startup_config(&mut self) -> Result<String> {
+        let command = self.show_config_command("show startup-config")?;
+        let output = self.send_command(&command)?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Saves the running configuration to the next archive file with `archive config`. The
+    /// archive has to be set up first, e.g. with [`ConfigurationMode::configure_archive`].
+    pub fn archive_config(&mut self) -> Result<()> {
+        self.expect_mode("archive config", &CiscoMode::PrivilegedExec)?;
+        self.execute_raw("archive config")
+    }
+
+    /// The archive files listed by `show archive`, the oldest first and the most recent last.
+    pub fn archives(&mut self) -> Result<Vec<String>> {
+        let output = self.send_command("show archive")?;
+        Ok(Self::parse_archives(&output))
+    }
+
+    /// Replaces the running configuration with a file like `flash:archive-3` or
+    /// `nvram:startup-config` using `configure replace <file> list force`, without asking for
+    /// confirmation. Returns the list of commands the device applied for it.
+    pub fn configure_replace(&mut self, file: &str) -> Result<String> {
+        let command = format!("configure replace {} list force", file);
+        self.configure_replace_with(&command)
+    }
+
+    /// Like [`CiscoDevice::configure_replace`], but the device goes back to the previous running
+    /// configuration by itself unless [`CiscoDevice::configure_confirm`] follows within the given
+    /// minutes.
+    pub fn configure_replace_confirmed(&mut self, file: &str, minutes: u32) -> Result<String> {
+        let command = format!("configure replace {} list force time {}", file, minutes);
+        self.configure_replace_with(&command)
+    }
+
+    /// Enter the configuration mode with `configure terminal revert timer <minutes>`, which works
+    /// like a Junos `commit confirmed`: the running configuration is archived first and restored
+    /// by the device unless [`CiscoDevice::configure_confirm`] follows within the given minutes,
+    /// so a change that cuts off the connection reverts itself. Needs a configured archive.
+    pub fn enter_config_confirmed(&mut self, minutes: u32) -> Result<ConfigurationMode<'_, Self>> {
+        let command = format!("configure terminal revert timer {}", minutes);
+        self.check_enter_config()?;
+        self.execute_raw(&command)?;
+        self.expect_mode(&command, &CiscoMode::GlobalConfig)?;
+        Ok(ConfigurationMode::enter(self))
+    }
+
+    /// Keep the changes made after [`CiscoDevice::enter_config_confirmed`] or
+    /// [`CiscoDevice::configure_replace_confirmed`] and stop the revert timer.
+    pub fn configure_confirm(&mut self) -> Result<()> {
+        self.expect_mode("configure confirm", &CiscoMode::PrivilegedExec)?;
+        self.execute_raw("configure confirm")
+    }
+
+    /// Go back to the archived configuration right away instead of waiting for the revert timer.
+    pub fn configure_revert_now(&mut self) -> Result<()> {
+        self.expect_mode("configure revert now", &CiscoMode::PrivilegedExec)?;
+        let output = self.send_command("configure revert now")?;
+        Self::check_rollback("configure revert now", output)?;
+        Ok(())
+    }
+
+    fn configure_replace_with(&mut self, command: &str) -> Result<String> {
+        self.expect_mode(command, &CiscoMode::PrivilegedExec)?;
+        let output = self.send_command(command)?;
+        Self::check_rollback(command, output)
+    }
+}
+
+impl<C> CiscoDevice<C> {
+    fn new(connection: C, prompt: &str) -> Result<CiscoDevice<C>> {
+        let base_prompt = Self::base_prompt_of(prompt).to_string();
+
         Ok(CiscoDevice {
-            connection: C::connect(addr, Some(username), Some(password))?,
-            prompt_end: Regex::new("#")?,
+            connection,
+            prompt_end: Self::prompt_pattern(&[&base_prompt])?,
+            mode: CiscoMode::from_prompt(&base_prompt, prompt).unwrap_or(CiscoMode::UserExec),
+            parent_modes: Vec::new(),
+            base_prompt,
+            error_patterns: ErrorPatterns::new(ERROR_PATTERNS)?,
         })
     }
 
-    pub fn enable(&mut self, password: &str) -> io::Result<()> {
-        self.execute_raw("enable")?;
-        if !password.is_empty() {
-            self.execute_raw(password)?;
+    /// The prompt of the device without the mode suffix, which is usually the hostname.
+    pub fn base_prompt(&self) -> &str {
+        &self.base_prompt
+    }
+
+    /// The mode the session is in, as read from the last prompt.
+    pub fn mode(&self) -> &CiscoMode {
+        &self.mode
+    }
+
+    /// The underlying connection, e.g. to check which SSH authentication method was used.
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// The underlying connection, e.g. to read the raw output of a command with
+    /// `send_command_raw`.
+    pub fn connection_mut(&mut self) -> &mut C {
+        &mut self.connection
+    }
+
+    /// The patterns which mark the output of a command as rejected by the device.
+    pub fn error_patterns(&self) -> &ErrorPatterns {
+        &self.error_patterns
+    }
+
+    /// Change the patterns which mark the output of a command as rejected by the device, e.g. to
+    /// add custom ones.
+    pub fn error_patterns_mut(&mut self) -> &mut ErrorPatterns {
+        &mut self.error_patterns
+    }
+
+    /// Reads the mode from the prompt at the end of the output. Output that doesn't end with a
+    /// prompt of the device keeps the current mode.
+    fn update_mode(&mut self, output: &str) {
+        if let Some(mode) = last_line(output).and_then(|prompt| CiscoMode::from_prompt(&self.base_prompt, prompt)) {
+            self.set_mode(mode);
+        }
+    }
+
+    /// Changes the mode and forgets the parent modes that were left on the way, e.g. when `exit`
+    /// went back to one of them or `end` left the configuration.
+    fn set_mode(&mut self, mode: CiscoMode) {
+        match self.parent_modes.iter().position(|parent| *parent == mode) {
+            Some(index) => self.parent_modes.truncate(index),
+            None if !mode.is_sub_config() => self.parent_modes.clear(),
+            None => {},
+        }
+        self.mode = mode;
+    }
+
+    /// Checks that the session is in the expected mode for, or after, a command.
+    fn expect_mode(&self, command: &str, expected: &CiscoMode) -> Result<()> {
+        if self.mode != *expected {
+            return Err(Error::WrongMode {
+                command: command.to_string(),
+                expected: expected.to_string(),
+                actual: self.mode.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that a command, sent from `previous`, led to a configuration sub-mode.
+    fn check_sub_mode(&self, command: &str, previous: &CiscoMode) -> Result<()> {
+        if self.mode == *previous || !self.mode.is_sub_config() {
+            return Err(Error::WrongMode {
+                command: command.to_string(),
+                expected: "a configuration sub-mode".to_string(),
+                actual: self.mode.to_string(),
+            });
         }
         Ok(())
     }
 
-    pub fn save(&mut self) -> io::Result<()> {
-        self.execute_raw("write memory")
+    /// Checks that `configure terminal` can be used, which only works from privileged EXEC mode.
+    fn check_enter_config(&self) -> Result<()> {
+        self.expect_mode("configure terminal", &CiscoMode::PrivilegedExec)
+    }
+
+    /// The mode `exit` leads to from the current mode: the mode a sub-mode was entered from, like
+    /// `config-router` for `config-router-af`, or global configuration mode if that isn't known.
+    /// Leaving the EXEC modes would log out, so it's refused.
+    fn mode_after_exit(&self) -> Result<CiscoMode> {
+        match self.mode {
+            CiscoMode::GlobalConfig => Ok(CiscoMode::PrivilegedExec),
+            CiscoMode::InterfaceConfig
+            | CiscoMode::RouterConfig
+            | CiscoMode::LineConfig
+            | CiscoMode::VlanConfig
+            | CiscoMode::OtherConfig(_) => Ok(self.parent_modes.last().cloned().unwrap_or(CiscoMode::GlobalConfig)),
+            CiscoMode::UserExec | CiscoMode::PrivilegedExec => Err(Error::WrongMode {
+                command: "exit".to_string(),
+                expected: "a configuration mode".to_string(),
+                actual: self.mode.to_string(),
+            }),
+        }
+    }
+
+    /// Prefixes a `show` command with `do` in the configuration modes. The configuration can't be
+    /// shown in user EXEC mode, so that is refused.
+    fn show_config_command(&self, command: &str) -> Result<String> {
+        match self.mode {
+            CiscoMode::UserExec => Err(Error::WrongMode {
+                command: command.to_string(),
+                expected: CiscoMode::PrivilegedExec.to_string(),
+                actual: self.mode.to_string(),
+            }),
+            _ if self.mode.is_config() => Ok(format!("do {}", command)),
+            _ => Ok(command.to_string()),
+        }
+    }
+
+    /// Removes the header IOS shows above the configuration, like `Building configuration...`,
+    /// `Current configuration : 1234 bytes` or `Using 1234 out of 524288 bytes`, and the blank
+    /// lines around the configuration.
+    fn clean_config(output: &str) -> String {
+        let mut lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+        let start = lines.iter()
+            .position(|line| {
+                !(line.is_empty()
+                    || *line == "Building configuration..."
+                    || line.starts_with("Current configuration :")
+                    || (line.starts_with("Using ") && line.contains(" out of ") && line.ends_with(" bytes")))
+            })
+            .unwrap_or(lines.len());
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.get(start..).unwrap_or_default().join("\n")
+    }
+
+    /// Reads the archive files from the output of `show archive` and orders them from the oldest to
+    /// the one marked as the most recent, since the numbers start over once the maximum is reached.
+    fn parse_archives(output: &str) -> Vec<String> {
+        let mut archives = Vec::new();
+        let mut most_recent = None;
+        for line in output.lines() {
+            let mut words = line.split_whitespace();
+            let (Some(number), Some(name)) = (words.next(), words.next()) else {
+                continue;
+            };
+            if number.parse::<u32>().is_err() {
+                continue;
+            }
+            if line.contains("<- Most Recent") {
+                most_recent = Some(archives.len());
+            }
+            archives.push(name.to_string());
+        }
+
+        if let Some(index) = most_recent {
+            archives.rotate_left(index + 1);
+        }
+        archives
+    }
+
+    /// Checks that a rollback finished with `Rollback Done`, and returns its output.
+    fn check_rollback(command: &str, output: String) -> Result<String> {
+        if !output.lines().any(|line| line.trim() == ROLLBACK_DONE) {
+            return Err(Error::CommandFailed {
+                command: command.to_string(),
+                output,
+            });
+        }
+        Ok(output)
+    }
+
+    fn save_command(&self) -> &'static str {
+        match self.mode.is_config() {
+            true => "do write memory",
+            false => "write memory",
+        }
+    }
+
+    /// Strips the mode suffix like `(config-if)#` or `>` from a prompt.
+    fn base_prompt_of(prompt: &str) -> &str {
+        let prompt = prompt.trim_end_matches(['>', '#']);
+        match prompt.find('(') {
+            Some(index) if prompt.ends_with(')') => &prompt[..index],
+            _ => prompt,
+        }
+    }
+
+    /// Builds a prompt pattern for the hostnames that matches all exec and configuration modes,
+    /// e.g. `sw01>`, `sw01#`, `sw01(config)#` or `sw01(config-if)#`.
+    fn prompt_pattern(base_prompts: &[&str]) -> Result<Regex> {
+        let base_prompts: Vec<String> = base_prompts.iter().map(|base_prompt| regex::escape(base_prompt)).collect();
+        Ok(Regex::new(&format!(r"^(?:{})(\([^)]*\))?[>#]$", base_prompts.join("|")))?)
+    }
+
+    /// The prompt to wait for after a command. `hostname <name>` changes the prompt right away,
+    /// so after it the prompt with the old or the new hostname is accepted. A rollback can bring
+    /// back any hostname, so after it every prompt is accepted.
+    fn prompt_end_for(&self, command: &str) -> Result<Regex> {
+        let command = command.trim();
+        if command.starts_with("configure replace ") || command == "configure revert now" {
+            return Ok(Regex::new(ANY_PROMPT)?);
+        }
+
+        let hostname = command.strip_prefix("hostname ").map(str::trim);
+        match hostname {
+            Some(hostname) if self.mode.is_config() && !hostname.is_empty() && !hostname.contains(' ') => {
+                Self::prompt_pattern(&[&self.base_prompt, hostname])
+            },
+            _ => Ok(self.prompt_end.clone()),
+        }
+    }
+
+    /// Reads the mode from the prompt after a command, and takes over the new hostname if the
+    /// prompt changed.
+    fn update_prompt(&mut self, prompt: &str) -> Result<()> {
+        match self.prompt_end.is_match(prompt) {
+            true => self.update_mode(prompt),
+            false => self.set_prompt(prompt)?,
+        }
+        Ok(())
+    }
+
+    /// Takes over the base prompt and the mode from a prompt of the device.
+    fn set_prompt(&mut self, prompt: &str) -> Result<()> {
+        self.base_prompt = Self::base_prompt_of(prompt).to_string();
+        self.prompt_end = Self::prompt_pattern(&[&self.base_prompt])?;
+        if let Some(mode) = CiscoMode::from_prompt(&self.base_prompt, prompt) {
+            self.set_mode(mode);
+        }
+        Ok(())
     }
 }
 
 impl<C: Connection> Configurable for CiscoDevice<C> {
     type SessionType = Self;
 
-    fn enter_config(&mut self) -> io::Result<ConfigurationMode<Self>> {
+    fn enter_config(&mut self) -> Result<ConfigurationMode<'_, Self>> {
+        self.check_enter_config()?;
         self.execute_raw("configure terminal")?;
+        self.expect_mode("configure terminal", &CiscoMode::GlobalConfig)?;
         Ok(ConfigurationMode::enter(self))
     }
 
-    fn execute_raw(&mut self, command: &str) -> io::Result<()> {
-        self.connection.execute_raw(command, &self.prompt_end)
+    fn execute_raw(&mut self, command: &str) -> Result<()> {
+        self.send_command(command)?;
+        Ok(())
+    }
+
+    fn send_command(&mut self, command: &str) -> Result<String> {
+        let prompt_end = self.prompt_end_for(command)?;
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &prompt_end)?;
+        self.update_prompt(&prompt)?;
+        self.error_patterns.check(command, output)
+    }
+
+    fn exit(&mut self) -> Result<()> {
+        let expected = self.mode_after_exit()?;
+        self.execute_raw("exit")?;
+        self.expect_mode("exit", &expected)
+    }
+
+    fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+        let previous = self.mode.clone();
+        self.execute_raw(command)?;
+        self.check_sub_mode(command, &previous)?;
+        self.parent_modes.push(previous);
+        Ok(())
     }
 
-    fn exit(&mut self) -> io::Result<()> {
-        self.execute_raw("exit")
+    fn exit_config(&mut self) -> Result<()> {
+        if !self.mode.is_config() {
+            return Ok(());
+        }
+        self.execute_raw("end")?;
+        self.expect_mode("end", &CiscoMode::PrivilegedExec)
     }
 }
 
 impl<'a, C: Connection> InterfaceConfigurable for ConfigurationMode<'a, CiscoDevice<C>> {
-    fn interface_up(&mut self, interface: &Interface) -> io::Result<()> {
-        self.session.execute_raw(&format!("interface {}", interface.name()))?;
-        self.session.execute_raw("no shutdown")?;
-        self.session.exit()
+    fn interface_up(&mut self, interface: &Interface) -> Result<()> {
+        let mut interface = self.interface(interface)?;
+        interface.execute_raw("no shutdown")?;
+        interface.exit()
+    }
+
+    fn interface_down(&mut self, interface: &Interface) -> Result<()> {
+        let mut interface = self.interface(interface)?;
+        interface.execute_raw("shutdown")?;
+        interface.exit()
+    }
+}
+
+impl<'a, C: Connection> ConfigurationMode<'a, CiscoDevice<C>> {
+    /// Enter the configuration of an interface with `interface <name>`.
+    pub fn interface(&mut self, interface: &Interface) -> Result<InterfaceMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("interface {}", interface.name()), CiscoMode::InterfaceConfig)
+    }
+
+    /// Enter the configuration of an OSPF process with `router ospf <process_id>`.
+    pub fn router_ospf(&mut self, process_id: u32) -> Result<RouterMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("router ospf {}", process_id), CiscoMode::RouterConfig)
+    }
+
+    /// Enter the configuration of the virtual terminal lines with `line vty <first> <last>`.
+    pub fn line_vty(&mut self, first: u32, last: u32) -> Result<LineMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("line vty {} {}", first, last), CiscoMode::LineConfig)
+    }
+
+    /// Enter the configuration of a VLAN with `vlan <id>`, which creates it if needed.
+    pub fn vlan(&mut self, id: u16) -> Result<VlanMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("vlan {}", id), CiscoMode::VlanConfig)
+    }
+
+    /// Set up the configuration archive with `archive`, which [`CiscoDevice::archive_config`] and
+    /// the revert timer of [`CiscoDevice::enter_config_confirmed`] need. The `path` is the prefix
+    /// of the archive files, like `flash:archive`, and at most `maximum` of them are kept.
+    pub fn configure_archive(&mut self, path: &str, maximum: u32) -> Result<()> {
+        let mut archive = self.enter_checked("archive", CiscoMode::OtherConfig("config-archive".to_string()))?;
+        archive.execute_raw(&format!("path {}", path))?;
+        archive.execute_raw(&format!("maximum {}", maximum))?;
+        archive.exit()
     }
 
-    fn interface_down(&mut self, interface: &Interface) -> io::Result<()> {
-        self.session.execute_raw(&format!("interface {}", interface.name()))?;
-        self.session.execute_raw("shutdown")?;
-        self.session.exit()
+    /// Enters a sub-mode and checks it's the expected one.
+    fn enter_checked(&mut self, command: &str, expected: CiscoMode) -> Result<SubMode<'_, CiscoDevice<C>>> {
+        let sub_mode = self.sub_mode(command)?;
+        sub_mode.session.expect_mode(command, &expected)?;
+        Ok(sub_mode)
     }
 }
 
 /// Reaches the save command through to the session, since cisco allows saving in config mode
 impl<'a, C: Connection<ConnectionHandler = C>> ConfigurationMode<'a, CiscoDevice<C>> {
-    pub fn save(&mut self) -> io::Result<()> {
+    pub fn save(&mut self) -> Result<()> {
         self.session.save()
     }
 }
+
+#[cfg(feature = "async")]
+impl<C: AsyncConnection<ConnectionHandler = C>> CiscoDevice<C> {
+    pub async fn connect_async<A: ToSocketAddrs + Send>(addr: A, username: &str, password: &str) -> Result<CiscoDevice<C>> {
+        Self::from_async_connection(C::connect(addr, Some(username), Some(password)).await?).await
+    }
+
+    /// Connect to the device with all the settings of a [`ConnectionBuilder`], like timeouts or a
+    /// custom port.
+    pub async fn connect_with_async(builder: &ConnectionBuilder) -> Result<CiscoDevice<C>> {
+        Self::from_async_connection(C::connect_with(builder).await?).await
+    }
+
+    /// Enter privileged EXEC mode. The secret is only sent if the device asks for it, and the
+    /// device has to end up in privileged EXEC mode, otherwise a [`Error::WrongMode`] is returned.
+    /// Does nothing if the session already is privileged.
+    pub async fn enable_async(&mut self, password: &str) -> Result<()> {
+        if self.mode != CiscoMode::UserExec {
+            return Ok(());
+        }
+
+        let password_prompt = Regex::new(r"[Pp]assword:?\s*$")?;
+        let prompt_end = Regex::new(&format!("{}|{}", password_prompt.as_str(), self.prompt_end.as_str()))?;
+
+        let line_ending = self.connection.line_ending().to_string();
+        self.connection.write_raw(format!("enable{}", line_ending).as_bytes()).await?;
+        let mut output = self.connection.read_until_prompt(&prompt_end).await
+            .map_err(|e| e.with_command("enable"))?;
+
+        // IOS asks again after a wrong secret, those attempts are answered with empty lines until
+        // it gives up. The password is never passed on as the command, so it doesn't end up in
+        // any error.
+        let mut answer = password;
+        let mut attempts = 0;
+        while password_prompt.is_match(&output) && attempts < ENABLE_ATTEMPTS {
+            self.connection.write_raw(format!("{}{}", answer, line_ending).as_bytes()).await?;
+            output = self.connection.read_until_prompt(&prompt_end).await
+                .map_err(|e| e.with_command("enable"))?;
+            answer = "";
+            attempts += 1;
+        }
+
+        self.update_mode(&output);
+        self.error_patterns.check("enable", output)?;
+        self.expect_mode("enable", &CiscoMode::PrivilegedExec)
+    }
+
+    /// Saves the running configuration like [`CiscoDevice::save`].
+    pub async fn save_async(&mut self) -> Result<()> {
+        let command = self.save_command();
+        AsyncConfigurable::execute_raw(self, command).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncConnection> CiscoDevice<C> {
+    /// Wrap an already established async connection and discover the prompt of the device.
+    pub async fn from_async_connection(mut connection: C) -> Result<CiscoDevice<C>> {
+        let prompt = connection.find_prompt(&Regex::new("[>#]")?).await?;
+        let mut device = Self::new(connection, &prompt)?;
+        device.prepare_session_async().await?;
+        Ok(device)
+    }
+
+    /// Prepares the session like [`CiscoDevice::prepare_session`]. This runs automatically when
+    /// connecting.
+    pub async fn prepare_session_async(&mut self) -> Result<()> {
+        AsyncConfigurable::execute_raw(self, "terminal length 0").await?;
+        AsyncConfigurable::execute_raw(self, "terminal width 511").await
+    }
+
+    /// Discover the prompt again like [`CiscoDevice::find_prompt`].
+    pub async fn find_prompt_async(&mut self) -> Result<String> {
+        let prompt = self.connection.find_prompt(&Regex::new(ANY_PROMPT)?).await?;
+        self.set_prompt(&prompt)?;
+        Ok(prompt)
+    }
+
+    /// Get the running configuration like [`CiscoDevice::get_running_config`].
+    pub async fn get_running_config_async(&mut self) -> Result<String> {
+        let command = self.show_config_command("show running-config")?;
+        let output = AsyncConfigurable::send_command(self, &command).await?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Get the startup configuration like [`CiscoDevice::get_startup_config`].
+    pub async fn get_startup_config_async(&mut self) -> Result<String> {
+        let command = self.show_config_command("show startup-config")?;
+        let output = AsyncConfigurable::send_command(self, &command).await?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Saves the running configuration to the archive like [`CiscoDevice::archive_config`].
+    pub async fn archive_config_async(&mut self) -> Result<()> {
+        self.expect_mode("archive config", &CiscoMode::PrivilegedExec)?;
+        AsyncConfigurable::execute_raw(self, "archive config").await
+    }
+
+    /// The archive files like [`CiscoDevice::archives`].
+    pub async fn archives_async(&mut self) -> Result<Vec<String>> {
+        let output = AsyncConfigurable::send_command(self, "show archive").await?;
+        Ok(Self::parse_archives(&output))
+    }
+
+    /// Replaces the running configuration like [`CiscoDevice::configure_replace`].
+    pub async fn configure_replace_async(&mut self, file: &str) -> Result<String> {
+        let command = format!("configure replace {} list force", file);
+        self.configure_replace_with_async(&command).await
+    }
+
+    /// Replaces the running configuration with a revert timer like
+    /// [`CiscoDevice::configure_replace_confirmed`].
+    pub async fn configure_replace_confirmed_async(&mut self, file: &str, minutes: u32) -> Result<String> {
+        let command = format!("configure replace {} list force time {}", file, minutes);
+        self.configure_replace_with_async(&command).await
+    }
+
+    /// Enter the configuration mode with a revert timer like
+    /// [`CiscoDevice::enter_config_confirmed`].
+    pub async fn enter_config_confirmed_async(&mut self, minutes: u32) -> Result<AsyncConfigurationMode<'_, Self>> {
+        let command = format!("configure terminal revert timer {}", minutes);
+        self.check_enter_config()?;
+        AsyncConfigurable::execute_raw(self, &command).await?;
+        self.expect_mode(&command, &CiscoMode::GlobalConfig)?;
+        Ok(AsyncConfigurationMode::enter(self))
+    }
+
+    /// Keep the changes and stop the revert timer like [`CiscoDevice::configure_confirm`].
+    pub async fn configure_confirm_async(&mut self) -> Result<()> {
+        self.expect_mode("configure confirm", &CiscoMode::PrivilegedExec)?;
+        AsyncConfigurable::execute_raw(self, "configure confirm").await
+    }
+
+    /// Go back to the archived configuration like [`CiscoDevice::configure_revert_now`].
+    pub async fn configure_revert_now_async(&mut self) -> Result<()> {
+        self.expect_mode("configure revert now", &CiscoMode::PrivilegedExec)?;
+        let output = AsyncConfigurable::send_command(self, "configure revert now").await?;
+        Self::check_rollback("configure revert now", output)?;
+        Ok(())
+    }
+
+    async fn configure_replace_with_async(&mut self, command: &str) -> Result<String> {
+        self.expect_mode(command, &CiscoMode::PrivilegedExec)?;
+        let output = AsyncConfigurable::send_command(self, command).await?;
+        Self::check_rollback(command, output)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<C: AsyncConnection> AsyncConfigurable for CiscoDevice<C> {
+    type SessionType = Self;
+
+    async fn enter_config(&mut self) -> Result<AsyncConfigurationMode<'_, Self>> {
+        self.check_enter_config()?;
+        self.execute_raw("configure terminal").await?;
+        self.expect_mode("configure terminal", &CiscoMode::GlobalConfig)?;
+        Ok(AsyncConfigurationMode::enter(self))
+    }
+
+    async fn execute_raw(&mut self, command: &str) -> Result<()> {
+        self.send_command(command).await?;
+        Ok(())
+    }
+
+    async fn send_command(&mut self, command: &str) -> Result<String> {
+        let prompt_end = self.prompt_end_for(command)?;
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &prompt_end).await?;
+        self.update_prompt(&prompt)?;
+        self.error_patterns.check(command, output)
+    }
+
+    async fn exit(&mut self) -> Result<()> {
+        let expected = self.mode_after_exit()?;
+        self.execute_raw("exit").await?;
+        self.expect_mode("exit", &expected)
+    }
+
+    async fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+        let previous = self.mode.clone();
+        self.execute_raw(command).await?;
+        self.check_sub_mode(command, &previous)?;
+        self.parent_modes.push(previous);
+        Ok(())
+    }
+
+    async fn exit_config(&mut self) -> Result<()> {
+        if !self.mode.is_config() {
+            return Ok(());
+        }
+        self.execute_raw("end").await?;
+        self.expect_mode("end", &CiscoMode::PrivilegedExec)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<'a, C: AsyncConnection> AsyncInterfaceConfigurable for AsyncConfigurationMode<'a, CiscoDevice<C>> {
+    async fn interface_up(&mut self, interface: &Interface) -> Result<()> {
+        let mut interface = self.interface(interface).await?;
+        let result = interface.execute_raw("no shutdown").await;
+        // Dropping an AsyncSubMode can't send `exit`, so it has to be left even if the command failed.
+        let exit = interface.exit().await;
+        result.and(exit)
+    }
+
+    async fn interface_down(&mut self, interface: &Interface) -> Result<()> {
+        let mut interface = self.interface(interface).await?;
+        let result = interface.execute_raw("shutdown").await;
+        let exit = interface.exit().await;
+        result.and(exit)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, C: AsyncConnection> AsyncConfigurationMode<'a, CiscoDevice<C>> {
+    /// Enter the configuration of an interface like [`ConfigurationMode::interface`].
+    pub async fn interface(&mut self, interface: &Interface) -> Result<AsyncSubMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("interface {}", interface.name()), CiscoMode::InterfaceConfig).await
+    }
+
+    /// Enter the configuration of an OSPF process like [`ConfigurationMode::router_ospf`].
+    pub async fn router_ospf(&mut self, process_id: u32) -> Result<AsyncSubMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("router ospf {}", process_id), CiscoMode::RouterConfig).await
+    }
+
+    /// Enter the configuration of the virtual terminal lines like [`ConfigurationMode::line_vty`].
+    pub async fn line_vty(&mut self, first: u32, last: u32) -> Result<AsyncSubMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("line vty {} {}", first, last), CiscoMode::LineConfig).await
+    }
+
+    /// Enter the configuration of a VLAN like [`ConfigurationMode::vlan`].
+    pub async fn vlan(&mut self, id: u16) -> Result<AsyncSubMode<'_, CiscoDevice<C>>> {
+        self.enter_checked(&format!("vlan {}", id), CiscoMode::VlanConfig).await
+    }
+
+    /// Set up the configuration archive like [`ConfigurationMode::configure_archive`].
+    pub async fn configure_archive(&mut self, path: &str, maximum: u32) -> Result<()> {
+        let mut archive = self.enter_checked("archive", CiscoMode::OtherConfig("config-archive".to_string())).await?;
+        let mut result = archive.execute_raw(&format!("path {}", path)).await;
+        if result.is_ok() {
+            result = archive.execute_raw(&format!("maximum {}", maximum)).await;
+        }
+        let exit = archive.exit().await;
+        result.and(exit)
+    }
+
+    /// Enters a sub-mode and checks it's the expected one.
+    async fn enter_checked(&mut self, command: &str, expected: CiscoMode) -> Result<AsyncSubMode<'_, CiscoDevice<C>>> {
+        let sub_mode = self.sub_mode(command).await?;
+        if let Err(e) = sub_mode.session.expect_mode(command, &expected) {
+            let _ = sub_mode.exit().await;
+            return Err(e);
+        }
+        Ok(sub_mode)
+    }
+}
+
+/// Reaches the save command through to the session, since cisco allows saving in config mode
+#[cfg(feature = "async")]
+impl<'a, C: AsyncConnection<ConnectionHandler = C>> AsyncConfigurationMode<'a, CiscoDevice<C>> {
+    pub async fn save(&mut self) -> Result<()> {
+        self.session.save_async().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::devices::cisco::{CiscoDevice, CiscoMode};
+    use crate::devices::generic::connection::MockConnection;
+    use crate::devices::generic::device_types::config::Configurable;
+    use crate::devices::generic::device_types::interfaces::Interface;
+    use crate::error::Error;
+
+    /// A mock that already answered the commands `from_connection` sends.
+    fn mock(prompt: &str) -> MockConnection {
+        MockConnection::new(prompt)
+            .expect("terminal length 0", "")
+            .expect("terminal width 511", "")
+    }
+
+    #[test]
+    fn reads_the_mode_from_the_first_prompt() {
+        let cisco = CiscoDevice::from_connection(mock("sw01>")).unwrap();
+        assert_eq!(cisco.base_prompt(), "sw01");
+        assert_eq!(*cisco.mode(), CiscoMode::UserExec);
+
+        let cisco = CiscoDevice::from_connection(mock("sw01#")).unwrap();
+        assert_eq!(*cisco.mode(), CiscoMode::PrivilegedExec);
+    }
+
+    #[test]
+    fn reads_past_the_greeting_of_the_session() {
+        let mock = mock("sw01>")
+            .greeting("\r\n*****************************\r\n* Authorized access only *\r\n*****************************\r\n\r\nsw01>")
+            .expect("show clock", "*12:00:00.000 UTC Mon May 1 2024");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        assert_eq!(cisco.send_command("show clock").unwrap(), "*12:00:00.000 UTC Mon May 1 2024");
+        cisco.connection().assert_done();
+    }
+
+    #[test]
+    fn enable_sends_the_secret_when_asked() {
+        let mock = mock("sw01>")
+            .expect_raw("enable", "enable\r\nPassword: ")
+            .expect_prompt("s3cret", "", "sw01#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        cisco.enable("s3cret").unwrap();
+        assert_eq!(*cisco.mode(), CiscoMode::PrivilegedExec);
+        cisco.connection().assert_done();
+    }
+
+    #[test]
+    fn enable_with_a_wrong_secret_fails() {
+        let mock = mock("sw01>")
+            .expect_raw("enable", "enable\r\nPassword: ")
+            .expect_raw("wrong", "\r\n% Bad secrets\r\n\r\nsw01>");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let error = cisco.enable("wrong").unwrap_err();
+        assert!(matches!(error, Error::CommandFailed { .. }), "{:?}", error);
+        assert_eq!(*cisco.mode(), CiscoMode::UserExec);
+        cisco.connection().assert_done();
+        assert!(!format!("{:?}", error).contains("wrong"), "the secret leaked into {:?}", error);
+    }
+
+    #[test]
+    fn enable_uses_the_line_ending_of_the_connection() {
+        let mock = mock("sw01>")
+            .line_ending("\r")
+            .expect_raw("enable", "enable\r\nPassword: ")
+            .expect_prompt("s3cret", "", "sw01#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        cisco.enable("s3cret").unwrap();
+        assert_eq!(*cisco.mode(), CiscoMode::PrivilegedExec);
+    }
+
+    #[test]
+    fn follows_the_modes_through_the_configuration() {
+        let mock = mock("sw01#")
+            .expect_prompt("configure terminal", "", "sw01(config)#")
+            .expect_prompt("interface gi0/1", "", "sw01(config-if)#")
+            .expect("description uplink", "")
+            .expect_prompt("exit", "", "sw01(config)#")
+            .expect_prompt("end", "", "sw01#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let mut config = cisco.enter_config().unwrap();
+        let mut interface = config.interface(&Interface::new("gi0/1")).unwrap();
+        assert_eq!(*interface.session.mode(), CiscoMode::InterfaceConfig);
+        interface.execute_raw("description uplink").unwrap();
+        interface.exit().unwrap();
+        assert_eq!(*config.session.mode(), CiscoMode::GlobalConfig);
+        config.finish().unwrap();
+
+        assert_eq!(*cisco.mode(), CiscoMode::PrivilegedExec);
+        cisco.connection().assert_done();
+    }
+
+    #[test]
+    fn exit_from_a_nested_sub_mode_goes_back_to_its_parent() {
+        let mock = mock("sw01#")
+            .expect_prompt("configure terminal", "", "sw01(config)#")
+            .expect_prompt("router bgp 65000", "", "sw01(config-router)#")
+            .expect_prompt("address-family ipv4 unicast", "", "sw01(config-router-af)#")
+            .expect("neighbor 10.0.0.2 activate", "")
+            .expect_prompt("exit", "", "sw01(config-router)#")
+            .expect_prompt("exit", "", "sw01(config)#")
+            .expect_prompt("end", "", "sw01#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let mut config = cisco.enter_config().unwrap();
+        let mut router = config.sub_mode("router bgp 65000").unwrap();
+        let mut address_family = router.sub_mode("address-family ipv4 unicast").unwrap();
+        assert_eq!(*address_family.session.mode(), CiscoMode::OtherConfig("config-router-af".to_string()));
+        address_family.execute_raw("neighbor 10.0.0.2 activate").unwrap();
+        address_family.exit().unwrap();
+        assert_eq!(*router.session.mode(), CiscoMode::RouterConfig);
+        router.exit().unwrap();
+        assert_eq!(*config.session.mode(), CiscoMode::GlobalConfig);
+        config.finish().unwrap();
+
+        cisco.connection().assert_done();
+    }
+
+    #[test]
+    fn refuses_the_configuration_in_user_exec() {
+        let mut cisco = CiscoDevice::from_connection(mock("sw01>")).unwrap();
+
+        let error = cisco.enter_config().err().unwrap();
+        assert!(matches!(error, Error::WrongMode { .. }), "{:?}", error);
+        assert_eq!(cisco.connection().sent().len(), 3);
+    }
+
+    #[test]
+    fn sub_mode_that_leads_elsewhere_is_a_wrong_mode() {
+        let mock = mock("sw01#")
+            .expect_prompt("configure terminal", "", "sw01(config)#")
+            .expect_prompt("interface gi0/1", "", "sw01(config-line)#")
+            .expect_prompt("exit", "", "sw01(config)#")
+            .expect_prompt("end", "", "sw01#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let mut config = cisco.enter_config().unwrap();
+        let error = config.interface(&Interface::new("gi0/1")).err().unwrap();
+        assert!(matches!(error, Error::WrongMode { .. }), "{:?}", error);
+        config.finish().unwrap();
+        cisco.connection().assert_done();
+    }
+
+    #[test]
+    fn rejected_commands_are_errors() {
+        let invalid = "              ^\r\n% Invalid input detected at '^' marker.\r\n";
+        let mock = mock("sw01#")
+            .expect("show ip intreface brief", invalid)
+            .expect("show clock", "*12:00:00.000 UTC Mon Mar 1 1993");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        match cisco.send_command("show ip intreface brief") {
+            Err(Error::CommandFailed { command, output }) => {
+                assert_eq!(command, "show ip intreface brief");
+                assert!(output.contains("% Invalid input detected"));
+            },
+            other => panic!("expected CommandFailed, got {:?}", other),
+        }
+        assert_eq!(cisco.send_command("show clock").unwrap(), "*12:00:00.000 UTC Mon Mar 1 1993");
+    }
+
+    #[test]
+    fn follows_a_new_hostname() {
+        let mock = mock("sw01#")
+            .expect_prompt("configure terminal", "", "sw01(config)#")
+            .expect_prompt("hostname core1", "", "core1(config)#")
+            .expect_prompt("end", "", "core1#");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let mut config = cisco.enter_config().unwrap();
+        config.execute_raw("hostname core1").unwrap();
+        config.finish().unwrap();
+
+        assert_eq!(cisco.base_prompt(), "core1");
+        assert_eq!(*cisco.mode(), CiscoMode::PrivilegedExec);
+    }
+
+    #[test]
+    fn missing_prompt_times_out_right_away() {
+        let mock = mock("sw01#").expect_raw("show version", "show version\r\nCisco IOS Software");
+        let mut cisco = CiscoDevice::from_connection(mock).unwrap();
+
+        let start = Instant::now();
+        match cisco.send_command("show version") {
+            Err(Error::Timeout { command, output }) => {
+                assert_eq!(command, "show version");
+                assert!(output.contains("Cisco IOS Software"));
+            },
+            other => panic!("expected Timeout, got {:?}", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
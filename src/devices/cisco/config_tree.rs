@@ -0,0 +1,339 @@
+use std::fmt;
+use regex::Regex;
+use crate::error::Result;
+
+/// An IOS configuration parsed into a tree of lines, where every line is the parent of the more
+/// indented lines that follow it, like `interface` with its settings.
+///
+/// It only needs the text of the configuration, e.g. from [`get_running_config`] or a file, so it
+/// works without a connection to the device. Lines are queried with regular expressions, which
+/// match anywhere in the line without its indentation unless they are anchored. The `!`
+/// separators and blank lines are dropped, and banners keep their text as it is.
+///
+/// ```
+/// use rustmiko::devices::cisco::CiscoConfig;
+///
+/// let config = CiscoConfig::parse("\
+/// hostname sw01
+/// !
+/// interface GigabitEthernet0/1
+///  description uplink
+///  shutdown
+/// !
+/// interface GigabitEthernet0/2
+///  switchport mode access
+/// !
+/// router bgp 65000
+///  neighbor 10.0.0.2 remote-as 65001
+///  address-family ipv4
+///   neighbor 10.0.0.2 activate
+///  exit-address-family
+/// !
+/// end");
+///
+/// let shutdown = config.find_parents_with_child(r"^interface ", r"^shutdown$")?;
+/// assert_eq!(shutdown[0].text(), "interface GigabitEthernet0/1");
+///
+/// let bgp = config.find(r"^router bgp")?;
+/// assert_eq!(bgp[0].children()[0].text(), "neighbor 10.0.0.2 remote-as 65001");
+/// assert_eq!(bgp[0].find_children(r"^address-family")?[0].children().len(), 1);
+///
+/// assert_eq!(config.find(r"^neighbor 10\.0\.0\.2")?.len(), 2);
+/// # Ok::<(), rustmiko::Error>(())
+/// ```
+///
+/// [`get_running_config`]: crate::devices::cisco::CiscoDevice::get_running_config
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CiscoConfig {
+    lines: Vec<ConfigLine>,
+}
+
+/// A line of the configuration with the lines below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigLine {
+    text: String,
+    indent: usize,
+    children: Vec<ConfigLine>,
+}
+
+impl CiscoConfig {
+    /// Parses the text of a configuration, like the output of `show running-config`.
+    pub fn parse(text: &str) -> CiscoConfig {
+        let mut lines = text.lines().map(str::trim_end).peekable();
+        CiscoConfig {
+            lines: ConfigLine::parse_children(&mut lines, None),
+        }
+    }
+
+    /// The lines at the top of the hierarchy, like `hostname` or `interface`.
+    pub fn lines(&self) -> &[ConfigLine] {
+        &self.lines
+    }
+
+    /// All lines anywhere in the hierarchy that match the pattern, in the order of the
+    /// configuration.
+    pub fn find(&self, pattern: &str) -> Result<Vec<&ConfigLine>> {
+        let pattern = Regex::new(pattern)?;
+        let mut found = Vec::new();
+        for line in &self.lines {
+            line.collect_matching(&pattern, &mut found);
+        }
+        Ok(found)
+    }
+
+    /// The lines at the top of the hierarchy that match the `parent` pattern and have a child
+    /// matching the `child` pattern, like the interfaces with `shutdown`.
+    pub fn find_parents_with_child(&self, parent: &str, child: &str) -> Result<Vec<&ConfigLine>> {
+        self.find_parents(parent, child, true)
+    }
+
+    /// The lines at the top of the hierarchy that match the `parent` pattern and have no child
+    /// matching the `child` pattern, like the interfaces without a `description`.
+    pub fn find_parents_without_child(&self, parent: &str, child: &str) -> Result<Vec<&ConfigLine>> {
+        self.find_parents(parent, child, false)
+    }
+
+    /// The children of all lines at the top of the hierarchy that match the `parent` pattern, like
+    /// the settings of `router bgp`.
+    pub fn find_children(&self, parent: &str) -> Result<Vec<&ConfigLine>> {
+        let parent = Regex::new(parent)?;
+        Ok(self.lines.iter()
+            .filter(|line| parent.is_match(&line.text))
+            .flat_map(|line| &line.children)
+            .collect())
+    }
+
+    fn find_parents(&self, parent: &str, child: &str, with_child: bool) -> Result<Vec<&ConfigLine>> {
+        let parent = Regex::new(parent)?;
+        let child = Regex::new(child)?;
+        Ok(self.lines.iter()
+            .filter(|line| parent.is_match(&line.text))
+            .filter(|line| line.has_child_matching(&child) == with_child)
+            .collect())
+    }
+}
+
+impl ConfigLine {
+    /// Parses the lines that are indented further than their parent, together with their own
+    /// children.
+    fn parse_children<'a>(lines: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>, parent: Option<usize>) -> Vec<ConfigLine> {
+        let mut children = Vec::new();
+        while let Some(&next) = lines.peek() {
+            if next.is_empty() || next.trim_start() == "!" {
+                lines.next();
+                continue;
+            }
+            let indent = next.len() - next.trim_start().len();
+            if parent.is_some_and(|parent| indent <= parent) {
+                break;
+            }
+            lines.next();
+
+            let text = next.trim_start().to_string();
+            let children_of_line = match Self::banner_delimiter(&text) {
+                Some(delimiter) => Self::parse_banner(lines, delimiter),
+                None => Self::parse_children(lines, Some(indent)),
+            };
+            children.push(ConfigLine {
+                text,
+                indent,
+                children: children_of_line,
+            });
+        }
+        children
+    }
+
+    /// The delimiter of a banner like `banner motd ^C`, if the banner text continues on the next
+    /// lines.
+    fn banner_delimiter(text: &str) -> Option<String> {
+        let mut words = text.splitn(3, ' ');
+        let (Some("banner"), Some(_), Some(rest)) = (words.next(), words.next(), words.next()) else {
+            return None;
+        };
+        let delimiter = match rest.starts_with("^C") {
+            true => "^C".to_string(),
+            false => rest.chars().next()?.to_string(),
+        };
+        match rest[delimiter.len()..].contains(&delimiter) {
+            true => None,
+            false => Some(delimiter),
+        }
+    }
+
+    /// Takes the lines of a banner up to the one with the closing delimiter, as they are.
+    fn parse_banner<'a>(lines: &mut impl Iterator<Item = &'a str>, delimiter: String) -> Vec<ConfigLine> {
+        let mut banner = Vec::new();
+        for line in lines.by_ref() {
+            banner.push(ConfigLine {
+                text: line.to_string(),
+                indent: 0,
+                children: Vec::new(),
+            });
+            if line.contains(&delimiter) {
+                break;
+            }
+        }
+        banner
+    }
+
+    /// The line without its indentation.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// How many spaces the line was indented by.
+    pub fn indent(&self) -> usize {
+        self.indent
+    }
+
+    /// The lines directly below this one.
+    pub fn children(&self) -> &[ConfigLine] {
+        &self.children
+    }
+
+    /// Whether the line matches the pattern.
+    pub fn is_match(&self, pattern: &str) -> Result<bool> {
+        Ok(Regex::new(pattern)?.is_match(&self.text))
+    }
+
+    /// The lines directly below this one that match the pattern.
+    pub fn find_children(&self, pattern: &str) -> Result<Vec<&ConfigLine>> {
+        let pattern = Regex::new(pattern)?;
+        Ok(self.children.iter().filter(|child| pattern.is_match(&child.text)).collect())
+    }
+
+    /// Whether any line directly below this one matches the pattern.
+    pub fn has_child(&self, pattern: &str) -> Result<bool> {
+        Ok(self.has_child_matching(&Regex::new(pattern)?))
+    }
+
+    fn has_child_matching(&self, pattern: &Regex) -> bool {
+        self.children.iter().any(|child| pattern.is_match(&child.text))
+    }
+
+    fn collect_matching<'a>(&'a self, pattern: &Regex, found: &mut Vec<&'a ConfigLine>) {
+        if pattern.is_match(&self.text) {
+            found.push(self);
+        }
+        for child in &self.children {
+            child.collect_matching(pattern, found);
+        }
+    }
+}
+
+/// Writes the configuration back as text, every line with its original indentation.
+impl fmt::Display for CiscoConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the line and all lines below it as text, every line with its original indentation.
+impl fmt::Display for ConfigLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:indent$}{}", "", self.text, indent = self.indent)?;
+        for child in &self.children {
+            write!(f, "{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CiscoConfig;
+
+    fn texts(config: &CiscoConfig) -> Vec<&str> {
+        config.lines().iter().map(|line| line.text()).collect()
+    }
+
+    #[test]
+    fn keeps_a_multi_line_banner_as_it_is() {
+        let config = CiscoConfig::parse("\
+hostname sw01
+banner motd ^C
+Authorized access only!
+!
+  Disconnect now if you are not an administrator.
+
+^C
+!
+line vty 0 4
+ login local
+end
+");
+
+        assert_eq!(texts(&config), ["hostname sw01", "banner motd ^C", "line vty 0 4", "end"]);
+        let banner: Vec<&str> = config.lines()[1].children().iter().map(|line| line.text()).collect();
+        assert_eq!(banner, [
+            "Authorized access only!",
+            "!",
+            "  Disconnect now if you are not an administrator.",
+            "",
+            "^C",
+        ]);
+        assert_eq!(config.find(r"^login local$").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn banner_with_a_single_character_delimiter() {
+        let config = CiscoConfig::parse("\
+banner exec #Welcome to sw01#
+banner login #
+Authorized access only
+#
+interface GigabitEthernet0/1
+ shutdown
+");
+
+        assert_eq!(texts(&config), ["banner exec #Welcome to sw01#", "banner login #", "interface GigabitEthernet0/1"]);
+        assert!(config.lines()[0].children().is_empty());
+        assert_eq!(config.lines()[1].children().len(), 2);
+        assert!(config.lines()[2].has_child(r"^shutdown$").unwrap());
+    }
+
+    #[test]
+    fn banner_text_starting_on_the_first_line() {
+        let config = CiscoConfig::parse("\
+banner motd ^CMaintenance tonight
+from 22:00 to 23:00^C
+hostname sw01
+");
+
+        assert_eq!(texts(&config), ["banner motd ^CMaintenance tonight", "hostname sw01"]);
+        assert_eq!(config.lines()[0].children()[0].text(), "from 22:00 to 23:00^C");
+    }
+
+    #[test]
+    fn round_trips_through_to_string() {
+        let text = "\
+hostname sw01
+banner motd ^C
+Authorized access only!
+!
+
+^C
+interface GigabitEthernet0/1
+ description uplink
+ switchport mode trunk
+router bgp 65000
+ neighbor 10.0.0.2 remote-as 65001
+ address-family ipv4
+  neighbor 10.0.0.2 activate
+ exit-address-family
+end
+";
+        let config = CiscoConfig::parse(text);
+        assert_eq!(config.to_string(), text);
+        assert_eq!(CiscoConfig::parse(&config.to_string()), config);
+    }
+
+    #[test]
+    fn drops_separators_and_blank_lines() {
+        let config = CiscoConfig::parse("hostname sw01\n!\n\ninterface Vlan1\n no ip address\n !\nend\n");
+        assert_eq!(config.to_string(), "hostname sw01\ninterface Vlan1\n no ip address\nend\n");
+    }
+}
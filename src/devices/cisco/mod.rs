@@ -1,10 +1,22 @@
 //! All cisco device types.
 mod cisco_api;
+mod config_tree;
+mod mode;
 
 pub use cisco_api::CiscoDevice;
+pub use config_tree::{CiscoConfig, ConfigLine};
+pub use mode::CiscoMode;
 
 
 use crate::devices::generic::connection::{SSHConnection, TelnetConnection};
 
 pub type CiscoSSH = CiscoDevice<SSHConnection>;
 pub type CiscoTelnet = CiscoDevice<TelnetConnection>;
+
+#[cfg(feature = "async")]
+use crate::devices::generic::connection::{AsyncSSHConnection, AsyncTelnetConnection};
+
+#[cfg(feature = "async")]
+pub type AsyncCiscoSSH = CiscoDevice<AsyncSSHConnection>;
+#[cfg(feature = "async")]
+pub type AsyncCiscoTelnet = CiscoDevice<AsyncTelnetConnection>;
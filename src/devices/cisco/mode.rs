@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// The mode an IOS session is in, as shown by the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiscoMode {
+    /// User EXEC mode, e.g. `sw01>`.
+    UserExec,
+    /// Privileged EXEC mode after `enable`, e.g. `sw01#`.
+    PrivilegedExec,
+    /// Global configuration mode after `configure terminal`, e.g. `sw01(config)#`.
+    GlobalConfig,
+    /// Interface configuration mode, e.g. `sw01(config-if)#`.
+    InterfaceConfig,
+    /// Routing protocol configuration mode after e.g. `router ospf 1`, `sw01(config-router)#`.
+    RouterConfig,
+    /// Line configuration mode after e.g. `line vty 0 4`, `sw01(config-line)#`.
+    LineConfig,
+    /// VLAN configuration mode after e.g. `vlan 10`, `sw01(config-vlan)#`.
+    VlanConfig,
+    /// Any other configuration sub-mode, with the name from the prompt like `config-router-af`.
+    OtherConfig(String),
+}
+
+impl CiscoMode {
+    /// Reads the mode from a prompt like `sw01(config-if)#`, which has to start with the base
+    /// prompt of the device.
+    pub fn from_prompt(base_prompt: &str, prompt: &str) -> Option<CiscoMode> {
+        let suffix = prompt.trim().strip_prefix(base_prompt)?;
+        match suffix {
+            ">" => Some(CiscoMode::UserExec),
+            "#" => Some(CiscoMode::PrivilegedExec),
+            "(config)#" => Some(CiscoMode::GlobalConfig),
+            "(config-if)#" | "(config-if-range)#" | "(config-subif)#" => Some(CiscoMode::InterfaceConfig),
+            "(config-router)#" => Some(CiscoMode::RouterConfig),
+            "(config-line)#" => Some(CiscoMode::LineConfig),
+            "(config-vlan)#" => Some(CiscoMode::VlanConfig),
+            _ => {
+                let name = suffix.strip_prefix('(')?.strip_suffix(")#")?;
+                Some(CiscoMode::OtherConfig(name.to_string()))
+            },
+        }
+    }
+
+    /// Whether this is one of the nested modes below global configuration mode.
+    pub fn is_sub_config(&self) -> bool {
+        self.is_config() && *self != CiscoMode::GlobalConfig
+    }
+
+    /// Whether this is global configuration mode or one of its sub-modes.
+    pub fn is_config(&self) -> bool {
+        !matches!(self, CiscoMode::UserExec | CiscoMode::PrivilegedExec)
+    }
+}
+
+impl fmt::Display for CiscoMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiscoMode::UserExec => write!(f, "user EXEC mode"),
+            CiscoMode::PrivilegedExec => write!(f, "privileged EXEC mode"),
+            CiscoMode::GlobalConfig => write!(f, "global configuration mode"),
+            CiscoMode::InterfaceConfig => write!(f, "interface configuration mode"),
+            CiscoMode::RouterConfig => write!(f, "router configuration mode"),
+            CiscoMode::LineConfig => write!(f, "line configuration mode"),
+            CiscoMode::VlanConfig => write!(f, "VLAN configuration mode"),
+            CiscoMode::OtherConfig(name) => write!(f, "configuration mode ({})", name),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::net::ToSocketAddrs;
+use std::time::Duration;
+use async_trait::async_trait;
+use log::debug;
+use regex::Regex;
+use tokio::time::Instant;
+use crate::devices::generic::connection::{last_line, normalize, strip_command_and_prompt, ConnectionBuilder, PromptBuffer, DEFAULT_READ_TIMEOUT};
+use crate::error::{Error, Result};
+
+/// The async counterpart of [`Connection`], driven by a tokio runtime. A single runtime can talk
+/// to many devices at the same time, without a thread per device.
+///
+/// Connecting, reading and writing specifically need to be implemented.
+///
+/// [`Connection`]: crate::devices::generic::connection::Connection
+#[async_trait]
+pub trait AsyncConnection: Send {
+	type ConnectionHandler;
+
+	/// Connects to the device with all the settings of a [`ConnectionBuilder`].
+	async fn connect_with(builder: &ConnectionBuilder) -> Result<Self::ConnectionHandler>;
+	/// Connects to the specified address using a Connection Handler.
+	async fn connect<A: ToSocketAddrs + Send>(addr: A, username: Option<&str>, password: Option<&str>) -> Result<Self::ConnectionHandler> {
+		let mut builder = ConnectionBuilder::from_addr(addr)?;
+		if let Some(username) = username {
+			builder = builder.username(username);
+		}
+		if let Some(password) = password {
+			builder = builder.password(password);
+		}
+		Self::connect_with(&builder).await
+	}
+	/// Writes raw bytes to the connection without waiting for any response.
+	async fn write_raw(&mut self, data: &[u8]) -> Result<()>;
+	/// Waits for the next chunk of data sent by the server.
+	async fn read_chunk(&mut self) -> Result<Vec<u8>>;
+
+	/// How long to wait for the prompt after a command was sent.
+	fn command_timeout(&self) -> Duration;
+
+	/// The line ending appended to every command.
+	fn line_ending(&self) -> &str;
+
+	/// How long the connection has to stay quiet in [`AsyncConnection::read_until_quiet`].
+	fn read_timeout(&self) -> Duration {
+		DEFAULT_READ_TIMEOUT
+	}
+
+	/// Reads input sent by the server until the prompt is found and returns everything that was
+	/// read, including the prompt itself. The output is [`normalize`]d.
+	///
+	/// Unlike the blocking [`Connection`], this doesn't poll with a read timeout but returns as
+	/// soon as the prompt arrived. If it doesn't show up within the command timeout, a
+	/// [`Error::Timeout`] containing the partial output is returned. Pagers are answered with a
+	/// space, like in [`Connection::read_until_prompt`].
+	///
+	/// [`Connection`]: crate::devices::generic::connection::Connection
+	/// [`Connection::read_until_prompt`]: crate::devices::generic::connection::Connection::read_until_prompt
+	async fn read_until_prompt(&mut self, prompt_end: &Regex) -> Result<String> {
+		self.read_until_prompt_raw(prompt_end).await.map(|output| normalize(&output))
+	}
+
+	/// Like [`AsyncConnection::read_until_prompt`], but returns the bytes exactly as they were
+	/// received, only without the pagers.
+	async fn read_until_prompt_raw(&mut self, prompt_end: &Regex) -> Result<Vec<u8>> {
+		let mut buffer = PromptBuffer::new(prompt_end)?;
+		let deadline = Instant::now() + self.command_timeout();
+
+		while let Ok(data) = tokio::time::timeout_at(deadline, self.read_chunk()).await {
+			let data = data?;
+
+			debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+			if buffer.push(&data) {
+				debug!("Found prompt. Ready for next command");
+				return Ok(buffer.into_raw());
+			}
+			if buffer.take_pager() {
+				debug!("Output stopped at the pager. Sending a space");
+				self.write_raw(b" ").await?;
+			}
+		}
+
+		Err(Error::Timeout {
+			command: String::new(),
+			output: buffer.into_string(),
+		})
+	}
+
+	/// Reads input sent by the server until the pattern matches anywhere in the output, like
+	/// [`Connection::read_until_pattern`].
+	///
+	/// [`Connection::read_until_pattern`]: crate::devices::generic::connection::Connection::read_until_pattern
+	async fn read_until_pattern(&mut self, pattern: &Regex) -> Result<String> {
+		let mut buffer = PromptBuffer::new(pattern)?;
+		let deadline = Instant::now() + self.command_timeout();
+
+		while let Ok(data) = tokio::time::timeout_at(deadline, self.read_chunk()).await {
+			let data = data?;
+
+			debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+			if buffer.push(&data) || buffer.contains(pattern) {
+				debug!("Found pattern");
+				return Ok(buffer.into_string());
+			}
+			if buffer.take_pager() {
+				debug!("Output stopped at the pager. Sending a space");
+				self.write_raw(b" ").await?;
+			}
+		}
+
+		Err(Error::Timeout {
+			command: String::new(),
+			output: buffer.into_string(),
+		})
+	}
+
+	/// Reads everything the server still sends until nothing arrives within the read timeout, like
+	/// [`Connection::read_until_quiet`].
+	///
+	/// [`Connection::read_until_quiet`]: crate::devices::generic::connection::Connection::read_until_quiet
+	async fn read_until_quiet(&mut self) -> Result<String> {
+		let mut output = Vec::new();
+		let deadline = Instant::now() + self.command_timeout();
+
+		while Instant::now() < deadline {
+			let Ok(data) = tokio::time::timeout(self.read_timeout(), self.read_chunk()).await else {
+				break;
+			};
+			let data = data?;
+
+			debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+			output.extend_from_slice(&data);
+		}
+
+		Ok(normalize(&output))
+	}
+
+	/// Sends an empty line and returns the prompt the device answers with, using a loose
+	/// `prompt_end` pattern to know when to stop reading. Everything that follows the prompt is
+	/// read until the connection goes quiet, like in [`Connection::find_prompt`].
+	///
+	/// [`Connection::find_prompt`]: crate::devices::generic::connection::Connection::find_prompt
+	async fn find_prompt(&mut self, prompt_end: &Regex) -> Result<String> {
+		let line_ending = self.line_ending().to_string();
+		self.write_raw(line_ending.as_bytes()).await?;
+		let mut output = self.read_until_prompt(prompt_end).await?;
+		output.push_str(&self.read_until_quiet().await?);
+
+		let prompt = last_line(&output).map(String::from);
+
+		prompt.ok_or_else(|| Error::PromptMismatch {
+			expected: prompt_end.to_string(),
+			output,
+		})
+	}
+
+	/// Reads input, sent by the server but ignores it.
+	async fn read_ignore(&mut self, prompt_end: &Regex) {
+		if let Err(e) = self.read_until_prompt(prompt_end).await {
+			debug!("Error discarded: {}", e);
+		}
+	}
+
+	/// Executes a raw string command on the connection.
+	async fn execute_raw(&mut self, command: &str, prompt_end: &Regex) -> Result<()> {
+		self.send_command(command, prompt_end).await?;
+		Ok(())
+	}
+
+	/// Sends a command and returns the output the device answered with. The echoed command and
+	/// the trailing prompt are removed from the output. The line ending is automatically appended.
+	async fn send_command(&mut self, command: &str, prompt_end: &Regex) -> Result<String> {
+		let (output, _) = self.send_command_with_prompt(command, prompt_end).await?;
+		Ok(output)
+	}
+
+	/// Like [`AsyncConnection::send_command`], but also returns the prompt the device answered with,
+	/// e.g. to track the mode the device is in.
+	async fn send_command_with_prompt(&mut self, command: &str, prompt_end: &Regex) -> Result<(String, String)> {
+		debug!("Wrote: {}", command);
+
+		let line = format!("{}{}", command, self.line_ending());
+		self.write_raw(line.as_bytes()).await?;
+		let output = self.read_until_prompt(prompt_end).await
+			.map_err(|e| e.with_command(command))?;
+
+		let prompt = last_line(&output).unwrap_or_default().to_string();
+		Ok((strip_command_and_prompt(&output, command, prompt_end), prompt))
+	}
+
+	/// Sends a command and returns everything the device answered with as it was received,
+	/// including the echoed command and the prompt.
+	async fn send_command_raw(&mut self, command: &str, prompt_end: &Regex) -> Result<Vec<u8>> {
+		debug!("Wrote: {}", command);
+
+		let line = format!("{}{}", command, self.line_ending());
+		self.write_raw(line.as_bytes()).await?;
+		self.read_until_prompt_raw(prompt_end).await
+			.map_err(|e| e.with_command(command))
+	}
+}
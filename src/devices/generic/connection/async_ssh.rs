@@ -0,0 +1,129 @@
+use std::io;
+use std::io::{Read, Write};
+use std::time::Duration;
+use async_trait::async_trait;
+use log::debug;
+use ssh2::{BlockDirections, Channel, Session};
+use tokio::io::Interest;
+use tokio::net::TcpStream;
+use crate::devices::generic::connection::{host_key, AsyncConnection, AuthMethod, ConnectionBuilder, SSHConnection};
+use crate::error::{Error, Result};
+
+/// The async counterpart of [`SSHConnection`].
+///
+/// Connecting and authenticating happens on tokio's blocking thread pool, since it reuses the
+/// blocking implementation. Afterwards the session is switched to non-blocking mode and reads and
+/// writes only wait for the socket to become ready.
+pub struct AsyncSSHConnection {
+	sess: Session,
+	channel: Channel,
+	socket: TcpStream,
+	auth_method: AuthMethod,
+	read_timeout: Duration,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl AsyncSSHConnection {
+	/// The SHA256 fingerprint of the host key of the server, e.g. to pin it for the next
+	/// connections.
+	pub fn host_key_fingerprint(&self) -> Option<String> {
+		host_key::host_key_fingerprint(&self.sess)
+	}
+
+	/// The authentication method that succeeded while connecting.
+	pub fn auth_method(&self) -> AuthMethod {
+		self.auth_method
+	}
+
+	/// Change how long to wait for the prompt after a command was sent, e.g. for commands that
+	/// take a long time to finish.
+	pub fn set_command_timeout(&mut self, timeout: Duration) {
+		self.command_timeout = timeout;
+	}
+}
+
+/// Runs a non-blocking libssh2 operation until it doesn't ask to be retried anymore, waiting for
+/// the socket in the direction libssh2 is blocked on in between.
+async fn retry<T>(socket: &TcpStream, sess: &Session, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+	match operation() {
+		Err(e) if e.kind() == io::ErrorKind::WouldBlock => {},
+		result => return result,
+	}
+
+	loop {
+		let interest = match sess.block_directions() {
+			BlockDirections::Outbound => Interest::WRITABLE,
+			BlockDirections::Both => Interest::READABLE | Interest::WRITABLE,
+			BlockDirections::Inbound | BlockDirections::None => Interest::READABLE,
+		};
+		socket.ready(interest).await?;
+
+		// try_io clears the readiness again if libssh2 still can't make progress.
+		match socket.try_io(interest, &mut operation) {
+			Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+			result => return result,
+		}
+	}
+}
+
+#[async_trait]
+impl AsyncConnection for AsyncSSHConnection {
+	type ConnectionHandler = AsyncSSHConnection;
+
+	/// Connect to the device using SSH. All authentication methods the builder has credentials
+	/// for are tried in order, until one of them succeeds.
+	async fn connect_with(builder: &ConnectionBuilder) -> Result<AsyncSSHConnection> {
+		let blocking_builder = builder.clone();
+		let (sess, channel, auth_method, socket) = tokio::task::spawn_blocking(move || {
+			let (sess, auth_method, socket) = SSHConnection::open_session(&blocking_builder)?;
+			let channel = SSHConnection::open_shell(&sess, &blocking_builder)?;
+			Ok::<_, Error>((sess, channel, auth_method, socket))
+		}).await.map_err(io::Error::other)??;
+
+		sess.set_blocking(false);
+		socket.set_nonblocking(true)?;
+
+		Ok(AsyncSSHConnection {
+			sess,
+			channel,
+			socket: TcpStream::from_std(socket)?,
+			auth_method,
+			read_timeout: builder.read_timeout,
+			command_timeout: builder.command_timeout,
+			line_ending: builder.line_ending.clone(),
+		})
+	}
+
+	async fn write_raw(&mut self, mut data: &[u8]) -> Result<()> {
+		while !data.is_empty() {
+			let size = retry(&self.socket, &self.sess, || self.channel.write(data)).await?;
+			data = &data[size..];
+		}
+		Ok(())
+	}
+
+	async fn read_chunk(&mut self) -> Result<Vec<u8>> {
+		let mut buf = [0u8; 1024];
+
+		let size = retry(&self.socket, &self.sess, || self.channel.read(&mut buf)).await?;
+		if size == 0 {
+			debug!("Channel closed by the server");
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Channel closed by the server").into());
+		}
+
+		Ok(buf[..size].to_vec())
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+
+	fn read_timeout(&self) -> Duration {
+		self.read_timeout
+	}
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use log::debug;
+use regex::Regex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use crate::devices::generic::connection::{AsyncConnection, ConnectionBuilder, DEFAULT_TELNET_PORT};
+use crate::error::{Error, Result};
+
+/// Interpret As Command, starts every telnet command.
+const IAC: u8 = 255;
+/// Starts a subnegotiation, which lasts until `IAC SE`.
+const SB: u8 = 250;
+/// Ends a subnegotiation.
+const SE: u8 = 240;
+/// The first of the option negotiation commands WILL, WONT, DO and DONT, which take an argument.
+const WILL: u8 = 251;
+
+/// Where the telnet filter stopped in the middle of a command.
+#[derive(Clone, Copy, PartialEq)]
+enum FilterState {
+	Data,
+	Command,
+	Negotiation,
+	Subnegotiation,
+	SubnegotiationCommand,
+}
+
+/// The async counterpart of [`TelnetConnection`].
+///
+/// Telnet commands sent by the server are skipped and never answered, just like the blocking
+/// connection does.
+///
+/// [`TelnetConnection`]: crate::devices::generic::connection::TelnetConnection
+pub struct AsyncTelnetConnection {
+	stream: TcpStream,
+	state: FilterState,
+	read_timeout: Duration,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl AsyncTelnetConnection {
+	/// Change how long to wait for the prompt after a command was sent, e.g. for commands that
+	/// take a long time to finish.
+	pub fn set_command_timeout(&mut self, timeout: Duration) {
+		self.command_timeout = timeout;
+	}
+
+	/// Removes telnet commands from the received bytes and unescapes `IAC IAC`. Commands may be
+	/// split over multiple reads, so the state is kept in between calls.
+	fn filter_commands(&mut self, data: &[u8]) -> Vec<u8> {
+		let mut output = Vec::with_capacity(data.len());
+
+		for &byte in data {
+			self.state = match (self.state, byte) {
+				(FilterState::Data, IAC) => FilterState::Command,
+				(FilterState::Data, _) => {
+					output.push(byte);
+					FilterState::Data
+				},
+				(FilterState::Command, IAC) => {
+					output.push(IAC);
+					FilterState::Data
+				},
+				(FilterState::Command, SB) => FilterState::Subnegotiation,
+				(FilterState::Command, WILL..) => FilterState::Negotiation,
+				(FilterState::Command, _) | (FilterState::Negotiation, _) => FilterState::Data,
+				(FilterState::Subnegotiation, IAC) => FilterState::SubnegotiationCommand,
+				(FilterState::Subnegotiation, _) => FilterState::Subnegotiation,
+				(FilterState::SubnegotiationCommand, SE) => FilterState::Data,
+				(FilterState::SubnegotiationCommand, _) => FilterState::Subnegotiation,
+			};
+		}
+
+		output
+	}
+}
+
+#[async_trait]
+impl AsyncConnection for AsyncTelnetConnection {
+	type ConnectionHandler = AsyncTelnetConnection;
+
+	/// Connect to the device using telnet with an optional username and password which are sent
+	/// to the device right after the connection is made. The whole login has to finish within the
+	/// connect timeout.
+	async fn connect_with(builder: &ConnectionBuilder) -> Result<AsyncTelnetConnection> {
+		let blocking_builder = builder.clone();
+		let (stream, _, _) = tokio::task::spawn_blocking(move || blocking_builder.connect_tcp(DEFAULT_TELNET_PORT))
+			.await
+			.map_err(std::io::Error::other)??;
+		stream.set_nonblocking(true)?;
+
+		let mut conn = AsyncTelnetConnection {
+			stream: TcpStream::from_std(stream)?,
+			state: FilterState::Data,
+			read_timeout: builder.read_timeout,
+			command_timeout: builder.connect_timeout,
+			line_ending: builder.line_ending.clone(),
+		};
+
+		// Authenticate
+		if let Some(username) = &builder.username {
+			conn.execute_raw(username, &Regex::new("[Pp]assword:?")?).await?;
+
+			if let Some(password) = &builder.password {
+				let line = format!("{}{}", password, conn.line_ending);
+				conn.write_raw(line.as_bytes()).await?;
+				let output = conn.read_until_prompt(&Regex::new("[>#%$]|[Uu]sername:?|[Ll]ogin:?")?).await?;
+
+				if Regex::new(r"([Uu]sername|[Ll]ogin):?\s*$")?.is_match(&output) {
+					return Err(Error::authentication("The device asked for the login again, the credentials were rejected"));
+				}
+			}
+		}
+
+		conn.command_timeout = builder.command_timeout;
+		Ok(conn)
+	}
+
+	/// Write data to the device, escaping every `IAC` byte in it.
+	async fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		let mut escaped = Vec::with_capacity(data.len());
+		for &byte in data {
+			if byte == IAC {
+				escaped.push(IAC);
+			}
+			escaped.push(byte);
+		}
+
+		self.stream.write_all(&escaped).await?;
+		Ok(())
+	}
+
+	/// Read the next chunk of data, skipping any telnet negotiation in between.
+	async fn read_chunk(&mut self) -> Result<Vec<u8>> {
+		let mut buf = [0u8; 1024];
+
+		loop {
+			let size = self.stream.read(&mut buf).await?;
+			if size == 0 {
+				debug!("Connection closed by the server");
+				return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Connection closed by the server").into());
+			}
+
+			let data = self.filter_commands(&buf[..size]);
+			if !data.is_empty() {
+				return Ok(data);
+			}
+		}
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+
+	fn read_timeout(&self) -> Duration {
+		self.read_timeout
+	}
+}
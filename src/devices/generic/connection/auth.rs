@@ -0,0 +1,162 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use log::debug;
+use ssh2::{KeyboardInteractivePrompt, Prompt, Session};
+use crate::devices::generic::connection::{ConnectionBuilder, PrivateKey};
+use crate::error::{Error, Result};
+
+/// A callback answering the prompts of keyboard-interactive authentication. It gets the
+/// instructions of the server and the prompts, and has to return one response per prompt.
+pub type KeyboardInteractiveHandler = Arc<dyn Fn(&str, &[Prompt]) -> Vec<String> + Send + Sync>;
+
+/// A method to authenticate against an SSH server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+	/// Use the keys of a running SSH agent.
+	Agent,
+	/// Use the private key set on the [`ConnectionBuilder`].
+	PublicKey,
+	/// Use the password set on the [`ConnectionBuilder`].
+	Password,
+	/// Answer the prompts of the server, e.g. from TACACS or RADIUS backed devices.
+	KeyboardInteractive,
+}
+
+impl AuthMethod {
+	/// The order in which the methods are tried, if no explicit order was set.
+	pub const DEFAULT_ORDER: [AuthMethod; 4] = [
+		AuthMethod::Agent,
+		AuthMethod::PublicKey,
+		AuthMethod::Password,
+		AuthMethod::KeyboardInteractive,
+	];
+
+	/// The name of the method in the SSH protocol.
+	pub fn protocol_name(&self) -> &'static str {
+		match self {
+			AuthMethod::Agent | AuthMethod::PublicKey => "publickey",
+			AuthMethod::Password => "password",
+			AuthMethod::KeyboardInteractive => "keyboard-interactive",
+		}
+	}
+}
+
+impl fmt::Display for AuthMethod {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			AuthMethod::Agent => f.write_str("agent"),
+			AuthMethod::PublicKey => f.write_str("public key"),
+			AuthMethod::Password => f.write_str("password"),
+			AuthMethod::KeyboardInteractive => f.write_str("keyboard-interactive"),
+		}
+	}
+}
+
+/// Answers keyboard-interactive prompts with the handler of the builder, or with the password for
+/// every prompt that doesn't echo.
+struct PromptAnswerer<'a> {
+	handler: Option<&'a KeyboardInteractiveHandler>,
+	password: Option<&'a str>,
+}
+
+impl KeyboardInteractivePrompt for PromptAnswerer<'_> {
+	fn prompt<'b>(&mut self, _username: &str, instructions: &str, prompts: &[Prompt<'b>]) -> Vec<String> {
+		if let Some(handler) = self.handler {
+			return handler(instructions, prompts);
+		}
+
+		prompts.iter()
+			.map(|prompt| match (prompt.echo, self.password) {
+				(false, Some(password)) => password.to_string(),
+				_ => String::new(),
+			})
+			.collect()
+	}
+}
+
+/// Authenticate the session by trying all methods of the builder in order, skipping the ones the
+/// server doesn't offer. Returns the method that succeeded.
+pub(crate) fn authenticate(sess: &Session, builder: &ConnectionBuilder) -> Result<AuthMethod> {
+	let Some(username) = &builder.username else {
+		return Err(Error::authentication("Can't connect to SSH without a username"));
+	};
+
+	let methods = builder.auth_chain();
+	if methods.is_empty() {
+		// Could also panic here because this should never happen as it's implemented on the
+		// device side
+		return Err(Error::authentication("Can't connect to SSH without any credentials"));
+	}
+
+	let offered = sess.auth_methods(username).map(String::from).unwrap_or_default();
+	debug!("Server offers authentication methods: {}", offered);
+
+	let mut last_error = None;
+	for method in &methods {
+		if !offered.is_empty() && !offered.split(',').any(|name| name == method.protocol_name()) {
+			debug!("Skipping {} authentication, the server doesn't offer it", method);
+			continue;
+		}
+
+		match try_method(sess, username, *method, builder) {
+			Ok(()) if sess.authenticated() => {
+				debug!("Authenticated using {}", method);
+				return Ok(*method);
+			},
+			Ok(()) => debug!("{} authentication didn't authenticate the session", method),
+			Err(e) => {
+				debug!("{} authentication failed: {}", method, e);
+				last_error = Some(e);
+			},
+		}
+	}
+
+	let tried = methods.iter().map(AuthMethod::to_string).collect::<Vec<String>>().join(", ");
+	Err(Error::Authentication {
+		message: format!("Couldn't authenticate properly against SSH Server, tried {}", tried),
+		source: last_error,
+	})
+}
+
+fn try_method(sess: &Session, username: &str, method: AuthMethod, builder: &ConnectionBuilder) -> std::result::Result<(), ssh2::Error> {
+	match method {
+		AuthMethod::Agent => sess.userauth_agent(username),
+		AuthMethod::PublicKey => match &builder.private_key {
+			Some(key) => authenticate_key(sess, username, key),
+			None => Ok(()),
+		},
+		AuthMethod::Password => match &builder.password {
+			Some(password) => sess.userauth_password(username, password),
+			None => Ok(()),
+		},
+		AuthMethod::KeyboardInteractive => {
+			let mut answerer = PromptAnswerer {
+				handler: builder.keyboard_interactive.as_ref(),
+				password: builder.password.as_deref(),
+			};
+			sess.userauth_keyboard_interactive(username, &mut answerer)
+		},
+	}
+}
+
+/// Authenticate using a private key from a file or from memory.
+fn authenticate_key(sess: &Session, username: &str, key: &PrivateKey) -> std::result::Result<(), ssh2::Error> {
+	match key {
+		PrivateKey::File { path, passphrase } => {
+			let mut public_key = path.clone().into_os_string();
+			public_key.push(".pub");
+			let public_key = PathBuf::from(public_key);
+			let public_key = public_key.exists().then_some(public_key.as_path());
+			sess.userauth_pubkey_file(username, public_key, path, passphrase.as_deref())
+		},
+		#[cfg(unix)]
+		PrivateKey::Memory { pem, passphrase } => {
+			sess.userauth_pubkey_memory(username, None, pem, passphrase.as_deref())
+		},
+		#[cfg(not(unix))]
+		PrivateKey::Memory { .. } => {
+			Err(ssh2::Error::new(ssh2::ErrorCode::Session(-1), "Private keys from memory are only supported on unix"))
+		},
+	}
+}
@@ -0,0 +1,144 @@
+use regex::Regex;
+use crate::devices::generic::connection::normalize;
+
+/// The pagers of IOS (`--More--`) and Junos (`---(more)---` or `---(more 42%)---`), which wait for
+/// a key before they show the rest of the output.
+const PAGER: &str = r"(--More--|---\(more( \d+%)?\)---)\s*$";
+
+/// A PromptBuffer collects everything the device sent over multiple reads and checks whether the
+/// gathered output ends with the prompt.
+///
+/// The prompt is only ever matched against the last line of the buffer and has to end right at
+/// the end of it, so prompts split over two reads are still found and prompt characters somewhere
+/// in the middle of the output are ignored. The last line is [`normalize`]d before matching, so
+/// escape sequences and redraws around the prompt don't get in the way.
+pub(crate) struct PromptBuffer {
+	data: Vec<u8>,
+	prompt_end: Regex,
+	pager: Regex,
+}
+
+impl PromptBuffer {
+	/// Creates an empty buffer that looks for the given prompt.
+	pub(crate) fn new(prompt_end: &Regex) -> Result<PromptBuffer, regex::Error> {
+		Ok(PromptBuffer {
+			data: Vec::new(),
+			prompt_end: Regex::new(&format!(r"(?:{})\s*$", prompt_end.as_str()))?,
+			pager: Regex::new(PAGER)?,
+		})
+	}
+
+	/// Appends freshly read data to the buffer. Returns true if the buffer now ends with the prompt.
+	pub(crate) fn push(&mut self, data: &[u8]) -> bool {
+		self.data.extend_from_slice(data);
+		self.found_prompt()
+	}
+
+	/// Checks whether the last line of the buffer is the prompt.
+	pub(crate) fn found_prompt(&self) -> bool {
+		self.prompt_end.is_match(self.last_line().trim_end())
+	}
+
+	/// Checks whether the pattern matches anywhere in the output gathered so far, which is
+	/// [`normalize`]d and trimmed at the end, unlike the prompt which is only matched on the last line.
+	pub(crate) fn contains(&self, pattern: &Regex) -> bool {
+		pattern.is_match(normalize(&self.data).trim_end())
+	}
+
+	/// Checks whether the output stopped at a pager and removes it from the buffer. If this
+	/// returns true, the device waits for a space to continue.
+	pub(crate) fn take_pager(&mut self) -> bool {
+		let last_line = self.last_line();
+		let Some(pager) = self.pager.find(&last_line) else {
+			return false;
+		};
+
+		let before_pager = last_line[..pager.start()].to_string();
+		self.data.truncate(self.last_line_start());
+		self.data.extend_from_slice(before_pager.as_bytes());
+		true
+	}
+
+	fn last_line(&self) -> String {
+		normalize(&self.data[self.last_line_start()..])
+	}
+
+	fn last_line_start(&self) -> usize {
+		self.data.iter()
+			.rposition(|&byte| byte == b'\n')
+			.map_or(0, |index| index + 1)
+	}
+
+	/// Consumes the buffer and returns all the output that was gathered, [`normalize`]d.
+	pub(crate) fn into_string(self) -> String {
+		normalize(&self.data)
+	}
+
+	/// Consumes the buffer and returns all the output that was gathered as it was received, only
+	/// without the pagers.
+	pub(crate) fn into_raw(self) -> Vec<u8> {
+		self.data
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use regex::Regex;
+	use super::PromptBuffer;
+
+	fn prompt_buffer() -> PromptBuffer {
+		PromptBuffer::new(&Regex::new(r"^sw01(\([^)]*\))?[>#]$").unwrap()).unwrap()
+	}
+
+	#[test]
+	fn finds_a_prompt_split_over_reads() {
+		let mut buffer = prompt_buffer();
+		assert!(!buffer.push(b"show clock\r\n*12:00:00.000 UTC Mon May 1 2024\r\nsw0"));
+		assert!(!buffer.push(b"1(conf"));
+		assert!(buffer.push(b"ig)#"));
+		assert_eq!(buffer.into_string(), "show clock\n*12:00:00.000 UTC Mon May 1 2024\nsw01(config)#");
+	}
+
+	#[test]
+	fn ignores_a_prompt_in_the_middle_of_the_output() {
+		let mut buffer = prompt_buffer();
+		assert!(!buffer.push(b"show history\r\nsw01#\r\n"));
+		assert!(!buffer.push(b"  show clock\r\n"));
+		assert!(buffer.push(b"\x1b[Ksw01# "));
+	}
+
+	#[test]
+	fn takes_the_ios_pager() {
+		let mut buffer = prompt_buffer();
+		assert!(!buffer.push(b"show running-config\r\nhostname sw01\r\n --More-- "));
+		assert!(buffer.take_pager());
+		assert!(!buffer.take_pager());
+
+		assert!(buffer.push(b"\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08          \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08end\r\nsw01#"));
+		assert_eq!(buffer.into_string(), "show running-config\nhostname sw01\nend\nsw01#");
+	}
+
+	#[test]
+	fn takes_the_junos_pager() {
+		let mut buffer = prompt_buffer();
+		assert!(!buffer.push(b"interfaces {\r\n---(more 42%)---"));
+		assert!(buffer.take_pager());
+		assert_eq!(buffer.into_string(), "interfaces {\n");
+
+		let mut buffer = prompt_buffer();
+		buffer.push(b"system {\r\n---(more)---");
+		assert!(buffer.take_pager());
+
+		let mut buffer = prompt_buffer();
+		buffer.push(b"    description \"--More-- is not at the end\";\r\n");
+		assert!(!buffer.take_pager());
+	}
+
+	#[test]
+	fn contains_matches_anywhere() {
+		let mut buffer = prompt_buffer();
+		assert!(!buffer.push(b"load merge terminal\r\n[Type ^D at a new line to end input]\r\n"));
+		assert!(buffer.contains(&Regex::new(r"(?m)^\[Type \^D at a new line to end input\]$").unwrap()));
+		assert!(!buffer.contains(&Regex::new(r"load complete").unwrap()));
+	}
+}
@@ -0,0 +1,448 @@
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use ssh2::Prompt;
+use crate::devices::generic::connection::{tunnel, AuthMethod, Connection, HostKeyPolicy, KeyboardInteractiveHandler, SSHConnection, TelnetConnection};
+#[cfg(feature = "async")]
+use crate::devices::generic::connection::AsyncConnection;
+use crate::error::{Error, Result};
+
+/// The default port used for SSH connections.
+pub const DEFAULT_SSH_PORT: u16 = 22;
+/// The default port used for Telnet connections.
+pub const DEFAULT_TELNET_PORT: u16 = 23;
+/// The default time to wait for the connection to be established and the login to finish.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+/// The default time a single read waits for data before checking on the command again.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(1);
+/// The default time to wait for the prompt after a command was sent.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where a [`ConnectionBuilder`] connects to.
+#[derive(Debug, Clone)]
+enum Target {
+	/// A host name or ip address, optionally with a port.
+	Host(String),
+	/// Already resolved socket addresses.
+	Addrs(Vec<SocketAddr>),
+}
+
+/// A private key used for SSH public key authentication.
+#[derive(Clone)]
+pub enum PrivateKey {
+	/// A private key read from a file. The public key is expected next to it with a `.pub`
+	/// extension, or is derived from the private key if it doesn't exist.
+	File {
+		path: PathBuf,
+		passphrase: Option<String>,
+	},
+	/// A PEM encoded private key held in memory, e.g. read from a secret store.
+	Memory {
+		pem: String,
+		passphrase: Option<String>,
+	},
+}
+
+impl std::fmt::Debug for PrivateKey {
+	/// Never prints the key material or the passphrase.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PrivateKey::File { path, .. } => f.debug_struct("File").field("path", path).finish_non_exhaustive(),
+			PrivateKey::Memory { .. } => f.debug_struct("Memory").finish_non_exhaustive(),
+		}
+	}
+}
+
+/// A ConnectionBuilder collects all the settings needed to open a [`Connection`], like the address,
+/// credentials, timeouts and terminal settings.
+///
+/// ```ignore
+/// let connection = ConnectionBuilder::new("192.168.1.101")
+///     .username("admin")
+///     .password("admin")
+///     .command_timeout(Duration::from_secs(120))
+///     .ssh()?;
+///
+/// let juniper = JuniperSSH::connect_with(
+///     &ConnectionBuilder::new("192.168.1.102")
+///         .username("automation")
+///         .private_key_file("/home/automation/.ssh/id_ed25519", Some("passphrase"))
+/// )?;
+/// ```
+///
+/// If no port is given, the default port of the transport is used (22 for SSH, 23 for Telnet).
+#[derive(Clone)]
+pub struct ConnectionBuilder {
+	target: Target,
+	port: Option<u16>,
+	pub(crate) username: Option<String>,
+	pub(crate) password: Option<String>,
+	pub(crate) private_key: Option<PrivateKey>,
+	pub(crate) use_agent: bool,
+	pub(crate) keyboard_interactive: Option<KeyboardInteractiveHandler>,
+	auth_methods: Option<Vec<AuthMethod>>,
+	pub(crate) host_key_policy: HostKeyPolicy,
+	pub(crate) known_hosts_file: Option<PathBuf>,
+	pub(crate) pinned_fingerprints: Vec<String>,
+	jump_hosts: Vec<ConnectionBuilder>,
+	proxy_command: Option<String>,
+	pub(crate) connect_timeout: Duration,
+	pub(crate) read_timeout: Duration,
+	pub(crate) command_timeout: Duration,
+	pub(crate) pty_type: String,
+	pub(crate) pty_size: Option<(u32, u32)>,
+	pub(crate) line_ending: String,
+}
+
+impl std::fmt::Debug for ConnectionBuilder {
+	/// Prints all settings, but never the password.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ConnectionBuilder")
+			.field("target", &self.target)
+			.field("port", &self.port)
+			.field("username", &self.username)
+			.field("password", &self.password.as_ref().map(|_| "<hidden>"))
+			.field("private_key", &self.private_key)
+			.field("use_agent", &self.use_agent)
+			.field("keyboard_interactive", &self.keyboard_interactive.is_some())
+			.field("auth_methods", &self.auth_methods)
+			.field("host_key_policy", &self.host_key_policy)
+			.field("known_hosts_file", &self.known_hosts_file)
+			.field("pinned_fingerprints", &self.pinned_fingerprints)
+			.field("jump_hosts", &self.jump_hosts)
+			.field("proxy_command", &self.proxy_command)
+			.field("connect_timeout", &self.connect_timeout)
+			.field("read_timeout", &self.read_timeout)
+			.field("command_timeout", &self.command_timeout)
+			.field("pty_type", &self.pty_type)
+			.field("pty_size", &self.pty_size)
+			.field("line_ending", &self.line_ending)
+			.finish()
+	}
+}
+
+impl ConnectionBuilder {
+	/// Create a new builder for the host, which can be a host name or ip address, with or without
+	/// a port.
+	pub fn new<S: Into<String>>(host: S) -> ConnectionBuilder {
+		Self::with_target(Target::Host(host.into()))
+	}
+
+	/// Create a new builder for already resolvable socket addresses.
+	pub fn from_addr<A: ToSocketAddrs>(addr: A) -> Result<ConnectionBuilder> {
+		Ok(Self::with_target(Target::Addrs(addr.to_socket_addrs()?.collect())))
+	}
+
+	fn with_target(target: Target) -> ConnectionBuilder {
+		ConnectionBuilder {
+			target,
+			port: None,
+			username: None,
+			password: None,
+			private_key: None,
+			use_agent: false,
+			keyboard_interactive: None,
+			auth_methods: None,
+			host_key_policy: HostKeyPolicy::default(),
+			known_hosts_file: None,
+			pinned_fingerprints: Vec::new(),
+			jump_hosts: Vec::new(),
+			proxy_command: None,
+			connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+			read_timeout: DEFAULT_READ_TIMEOUT,
+			command_timeout: DEFAULT_COMMAND_TIMEOUT,
+			pty_type: "rustmiko".to_string(),
+			pty_size: None,
+			line_ending: "\n".to_string(),
+		}
+	}
+
+	/// Set the port to connect to, overriding the default port of the transport.
+	pub fn port(mut self, port: u16) -> Self {
+		self.port = Some(port);
+		self
+	}
+
+	/// Set the username used to log in.
+	pub fn username<S: Into<String>>(mut self, username: S) -> Self {
+		self.username = Some(username.into());
+		self
+	}
+
+	/// Set the password used to log in.
+	pub fn password<S: Into<String>>(mut self, password: S) -> Self {
+		self.password = Some(password.into());
+		self
+	}
+
+	/// Authenticate using a private key file, optionally protected by a passphrase. Only used by
+	/// SSH.
+	pub fn private_key_file<P: Into<PathBuf>>(mut self, path: P, passphrase: Option<&str>) -> Self {
+		self.private_key = Some(PrivateKey::File {
+			path: path.into(),
+			passphrase: passphrase.map(String::from),
+		});
+		self
+	}
+
+	/// Authenticate using a PEM encoded private key from memory, optionally protected by a
+	/// passphrase. Only used by SSH.
+	pub fn private_key<S: Into<String>>(mut self, pem: S, passphrase: Option<&str>) -> Self {
+		self.private_key = Some(PrivateKey::Memory {
+			pem: pem.into(),
+			passphrase: passphrase.map(String::from),
+		});
+		self
+	}
+
+	/// Try to authenticate using the keys of a running SSH agent. Only used by SSH.
+	pub fn agent(mut self) -> Self {
+		self.use_agent = true;
+		self
+	}
+
+	/// Answer keyboard-interactive prompts with a callback, which gets the instructions of the
+	/// server and the prompts and returns one response per prompt. Only used by SSH.
+	///
+	/// Without a callback, every prompt that doesn't echo is answered with the password.
+	pub fn keyboard_interactive<F>(mut self, handler: F) -> Self
+	where
+		F: Fn(&str, &[Prompt]) -> Vec<String> + Send + Sync + 'static,
+	{
+		self.keyboard_interactive = Some(Arc::new(handler));
+		self
+	}
+
+	/// Set the authentication methods to try and their order. Only used by SSH.
+	///
+	/// By default all methods with credentials are tried in the order of
+	/// [`AuthMethod::DEFAULT_ORDER`]: agent (if enabled), public key, password and
+	/// keyboard-interactive.
+	pub fn auth_methods(mut self, methods: &[AuthMethod]) -> Self {
+		self.auth_methods = Some(methods.to_vec());
+		self
+	}
+
+	/// Set how the host key of the server is verified. Only used by SSH.
+	///
+	/// The default is [`HostKeyPolicy::AcceptNew`], which trusts a host on the first connection and
+	/// rejects it if its key changes later. [`HostKeyPolicy::Insecure`] skips the check and logs a
+	/// warning on every connection.
+	pub fn host_key_policy(mut self, policy: HostKeyPolicy) -> Self {
+		self.host_key_policy = policy;
+		self
+	}
+
+	/// Set the known hosts file to verify the host key against, `~/.ssh/known_hosts` by default.
+	/// Only used by SSH.
+	pub fn known_hosts_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+		self.known_hosts_file = Some(path.into());
+		self
+	}
+
+	/// Pin the SHA256 fingerprint of the expected host key, in the format printed by OpenSSH
+	/// (`SHA256:...`). Can be called multiple times to allow multiple keys. If any fingerprint is
+	/// pinned, the known hosts file isn't used. Only used by SSH.
+	pub fn pin_fingerprint<S: Into<String>>(mut self, fingerprint: S) -> Self {
+		self.pinned_fingerprints.push(fingerprint.into());
+		self
+	}
+
+	/// Connect through an SSH jump host. The jump host is configured with its own builder, so it
+	/// can use different credentials and host key settings. Calling this multiple times chains the
+	/// jump hosts in the given order.
+	///
+	/// ```ignore
+	/// let bastion = ConnectionBuilder::new("bastion.example.com")
+	///     .username("jump")
+	///     .private_key_file("/home/automation/.ssh/id_ed25519", None);
+	///
+	/// let cisco = CiscoSSH::connect_with(
+	///     &ConnectionBuilder::new("10.0.0.1")
+	///         .username("admin")
+	///         .password("admin")
+	///         .jump_host(bastion)
+	/// )?;
+	/// ```
+	pub fn jump_host(mut self, jump_host: ConnectionBuilder) -> Self {
+		self.jump_hosts.push(jump_host);
+		self
+	}
+
+	/// Connect through the stdin and stdout of a command instead of a TCP connection, like the
+	/// `ProxyCommand` of OpenSSH. `%h` is replaced with the host and `%p` with the port, e.g.
+	/// `ssh -W %h:%p bastion`. Takes precedence over jump hosts.
+	pub fn proxy_command<S: Into<String>>(mut self, command: S) -> Self {
+		self.proxy_command = Some(command.into());
+		self
+	}
+
+	/// Set how long connecting, the SSH handshake and the login may take.
+	pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+		self.connect_timeout = timeout;
+		self
+	}
+
+	/// Set how long a single read waits for data. This is the granularity in which the command
+	/// timeout is checked, and how long the connection has to stay quiet after the prompt was
+	/// discovered.
+	pub fn read_timeout(mut self, timeout: Duration) -> Self {
+		self.read_timeout = timeout;
+		self
+	}
+
+	/// Set how long to wait for the prompt after a command was sent.
+	pub fn command_timeout(mut self, timeout: Duration) -> Self {
+		self.command_timeout = timeout;
+		self
+	}
+
+	/// Set the terminal type requested for the PTY. Only used by SSH.
+	pub fn pty_type<S: Into<String>>(mut self, pty_type: S) -> Self {
+		self.pty_type = pty_type.into();
+		self
+	}
+
+	/// Set the size of the PTY in characters. Only used by SSH.
+	pub fn pty_size(mut self, width: u32, height: u32) -> Self {
+		self.pty_size = Some((width, height));
+		self
+	}
+
+	/// Set the line ending appended to every command, `\n` by default.
+	pub fn line_ending<S: Into<String>>(mut self, line_ending: S) -> Self {
+		self.line_ending = line_ending.into();
+		self
+	}
+
+	/// Connect using any connection type.
+	pub fn connect<C: Connection>(&self) -> Result<C::ConnectionHandler> {
+		C::connect_with(self)
+	}
+
+	/// Connect using SSH.
+	pub fn ssh(&self) -> Result<SSHConnection> {
+		self.connect::<SSHConnection>()
+	}
+
+	/// Connect using Telnet.
+	pub fn telnet(&self) -> Result<TelnetConnection> {
+		self.connect::<TelnetConnection>()
+	}
+
+	/// Connect using any async connection type.
+	#[cfg(feature = "async")]
+	pub async fn connect_async<C: AsyncConnection>(&self) -> Result<C::ConnectionHandler> {
+		C::connect_with(self).await
+	}
+
+	/// The authentication methods to try in order.
+	pub(crate) fn auth_chain(&self) -> Vec<AuthMethod> {
+		if let Some(methods) = &self.auth_methods {
+			return methods.clone();
+		}
+
+		AuthMethod::DEFAULT_ORDER.into_iter()
+			.filter(|method| match method {
+				AuthMethod::Agent => self.use_agent,
+				AuthMethod::PublicKey => self.private_key.is_some(),
+				AuthMethod::Password => self.password.is_some(),
+				AuthMethod::KeyboardInteractive => self.password.is_some() || self.keyboard_interactive.is_some(),
+			})
+			.collect()
+	}
+
+	/// The host name or ip address without the port, if the builder was created from a host.
+	fn host(&self) -> Option<String> {
+		match &self.target {
+			Target::Host(host) => Some(split_host_port(host).0),
+			Target::Addrs(_) => None,
+		}
+	}
+
+	/// Resolve the target to socket addresses, using the default port if none was given.
+	pub(crate) fn socket_addrs(&self, default_port: u16) -> Result<Vec<SocketAddr>> {
+		let mut addrs: Vec<SocketAddr> = match &self.target {
+			Target::Host(host) => match self.port {
+				Some(port) => (host.as_str(), port).to_socket_addrs()?.collect(),
+				None => match host.to_socket_addrs() {
+					Ok(addrs) => addrs.collect(),
+					Err(_) => (host.as_str(), default_port).to_socket_addrs()?.collect(),
+				},
+			},
+			Target::Addrs(addrs) => addrs.clone(),
+		};
+
+		if let Some(port) = self.port {
+			addrs.iter_mut().for_each(|addr| addr.set_port(port));
+		}
+		if addrs.is_empty() {
+			return Err(Error::NoAddress);
+		}
+		Ok(addrs)
+	}
+
+	/// The host and port to connect to, without resolving the host name. Used for tunnels, where
+	/// the name is resolved on the other end.
+	fn endpoint(&self, default_port: u16) -> Result<(String, u16)> {
+		match &self.target {
+			Target::Host(host) => {
+				let (name, port) = split_host_port(host);
+				Ok((name, self.port.or(port).unwrap_or(default_port)))
+			},
+			Target::Addrs(addrs) => {
+				let addr = addrs.first().ok_or(Error::NoAddress)?;
+				Ok((addr.ip().to_string(), self.port.unwrap_or(addr.port())))
+			},
+		}
+	}
+
+	/// Open a stream to the device within the connect timeout, either directly, through the jump
+	/// hosts or through the proxy command. Also returns the host and port that were connected to,
+	/// which are used to verify the host key.
+	pub(crate) fn connect_tcp(&self, default_port: u16) -> Result<(TcpStream, String, u16)> {
+		if let Some(command) = &self.proxy_command {
+			let (host, port) = self.endpoint(default_port)?;
+			return Ok((tunnel::proxy_command(command, &host, port)?, host, port));
+		}
+
+		if let Some((last, others)) = self.jump_hosts.split_last() {
+			// The last jump host is reached through all the ones before it.
+			let mut last = last.clone();
+			last.jump_hosts.splice(0..0, others.iter().cloned());
+
+			let (host, port) = self.endpoint(default_port)?;
+			let (session, _, _) = SSHConnection::open_session(&last)?;
+			let channel = session.channel_direct_tcpip(&host, port, None)?;
+			return Ok((tunnel::forward_channel(session, channel)?, host, port));
+		}
+
+		let mut last_error = Error::NoAddress;
+		for addr in self.socket_addrs(default_port)? {
+			match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+				Ok(stream) => {
+					let host = self.host().unwrap_or_else(|| addr.ip().to_string());
+					return Ok((stream, host, addr.port()));
+				},
+				Err(e) => last_error = e.into(),
+			}
+		}
+		Err(last_error)
+	}
+}
+
+/// Splits a host like `10.0.0.1:2222`, `[::1]:22` or `switch` into the name and the port.
+fn split_host_port(host: &str) -> (String, Option<u16>) {
+	if let Ok(addr) = host.parse::<SocketAddr>() {
+		return (addr.ip().to_string(), Some(addr.port()));
+	}
+
+	match host.rsplit_once(':') {
+		Some((name, port)) if !name.contains(':') => match port.parse() {
+			Ok(port) => (name.to_string(), Some(port)),
+			Err(_) => (host.to_string(), None),
+		},
+		_ => (host.to_string(), None),
+	}
+}
@@ -0,0 +1,133 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use log::{debug, warn};
+use ssh2::{CheckResult, HashType, HostKeyType, KnownHostFileKind, Session};
+use crate::devices::generic::connection::ConnectionBuilder;
+use crate::error::{Error, Result};
+
+/// Decides what happens with the host key of an SSH server during the connection. The default is
+/// [`HostKeyPolicy::AcceptNew`], like `StrictHostKeyChecking accept-new` of OpenSSH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HostKeyPolicy {
+	/// The host key has to be in the known hosts file already. Unknown hosts are rejected.
+	Strict,
+	/// Unknown hosts are accepted and added to the known hosts file, but a changed host key is
+	/// rejected.
+	#[default]
+	AcceptNew,
+	/// The host key isn't checked at all, which has to be chosen explicitly. Don't use this in
+	/// production.
+	Insecure,
+}
+
+/// Returns the SHA256 fingerprint of the host key in the OpenSSH format, e.g. `SHA256:abc...`.
+pub fn host_key_fingerprint(sess: &Session) -> Option<String> {
+	sess.host_key_hash(HashType::Sha256)
+		.map(|hash| format!("SHA256:{}", base64(hash).trim_end_matches('=')))
+}
+
+/// The default known hosts file of the current user.
+pub(crate) fn default_known_hosts_file() -> Option<PathBuf> {
+	std::env::var_os("HOME").map(|home| Path::new(&home).join(".ssh").join("known_hosts"))
+}
+
+/// Verify the host key of the server against the pinned fingerprints and the known hosts file.
+///
+/// If fingerprints are pinned, the key has to match one of them and the known hosts file isn't
+/// used. Otherwise the [`HostKeyPolicy`] of the builder decides.
+pub(crate) fn verify_host_key(sess: &Session, host: &str, port: u16, builder: &ConnectionBuilder) -> Result<()> {
+	let (key, key_type) = sess.host_key()
+		.ok_or_else(|| Error::authentication("The server didn't send a host key"))?;
+	let fingerprint = host_key_fingerprint(sess).unwrap_or_default();
+
+	if !builder.pinned_fingerprints.is_empty() {
+		let normalize = |fingerprint: &str| fingerprint.trim_start_matches("SHA256:").trim_end_matches('=').to_string();
+		if builder.pinned_fingerprints.iter().any(|pinned| normalize(pinned) == normalize(&fingerprint)) {
+			debug!("Host key of {} matches the pinned fingerprint", host);
+			return Ok(());
+		}
+		return Err(Error::HostKeyMismatch {
+			host: host.to_string(),
+			fingerprint,
+		});
+	}
+
+	if builder.host_key_policy == HostKeyPolicy::Insecure {
+		warn!(
+			"Not verifying the host key of {} ({}) with HostKeyPolicy::Insecure, which doesn't protect against man-in-the-middle attacks",
+			host, fingerprint,
+		);
+		return Ok(());
+	}
+
+	let Some(path) = builder.known_hosts_file.clone().or_else(default_known_hosts_file) else {
+		return Err(Error::authentication("No known hosts file was set and $HOME isn't set"));
+	};
+
+	let mut known_hosts = sess.known_hosts()?;
+	if path.exists() {
+		known_hosts.read_file(&path, KnownHostFileKind::OpenSSH)?;
+	}
+
+	match known_hosts.check_port(host, port, key) {
+		CheckResult::Match => Ok(()),
+		CheckResult::Mismatch => Err(Error::HostKeyMismatch {
+			host: host.to_string(),
+			fingerprint,
+		}),
+		CheckResult::NotFound if builder.host_key_policy == HostKeyPolicy::AcceptNew => {
+			warn!("Adding new host key of {} ({}) to {}", host, fingerprint, path.display());
+			append_known_host(&path, host, port, key, key_type)
+		},
+		CheckResult::NotFound => Err(Error::HostKeyUnknown {
+			host: host.to_string(),
+			fingerprint,
+		}),
+		CheckResult::Failure => Err(Error::authentication("Couldn't check the host key against the known hosts file")),
+	}
+}
+
+/// Append a host to the known hosts file, without rewriting the entries that are already in it.
+fn append_known_host(path: &Path, host: &str, port: u16, key: &[u8], key_type: HostKeyType) -> Result<()> {
+	let key_name = match key_type {
+		HostKeyType::Rsa => "ssh-rsa",
+		HostKeyType::Dss => "ssh-dss",
+		HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+		HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+		HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+		HostKeyType::Ed25519 => "ssh-ed25519",
+		HostKeyType::Unknown => return Err(Error::authentication("The server sent a host key of unknown type")),
+	};
+	let host = match port {
+		22 => host.to_string(),
+		port => format!("[{}]:{}", host, port),
+	};
+
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+	writeln!(file, "{} {} {}", host, key_name, base64(key))?;
+	Ok(())
+}
+
+/// Standard base64 with padding, as used by the known hosts file and fingerprints.
+fn base64(data: &[u8]) -> String {
+	const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+	let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+	for chunk in data.chunks(3) {
+		let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+		let triple = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+		for index in 0..4 {
+			if index <= chunk.len() {
+				encoded.push(ALPHABET[(triple >> (18 - 6 * index) & 0x3f) as usize] as char);
+			} else {
+				encoded.push('=');
+			}
+		}
+	}
+	encoded
+}
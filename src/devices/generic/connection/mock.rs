@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+use crate::devices::generic::connection::{normalize, Connection, ConnectionBuilder};
+use crate::error::{Error, Result};
+
+/// The command timeout a MockConnection reports by default. Everything is answered right away, so
+/// it never waits for it.
+const DEFAULT_MOCK_COMMAND_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// What the mock answers with after a scripted command.
+#[derive(Debug, Clone)]
+enum Reply {
+	/// The echoed command, the output and the prompt. An optional new prompt replaces the current
+	/// one, e.g. after entering the configuration mode.
+	Output {
+		output: String,
+		prompt: Option<String>,
+	},
+	/// Exactly these bytes, without the echo or a prompt.
+	Raw(String),
+	/// The connection is closed by the device.
+	Disconnect,
+}
+
+#[derive(Debug, Clone)]
+struct Expectation {
+	command: String,
+	reply: Reply,
+}
+
+/// A MockConnection is an in-memory Connection, which answers a script of expected commands with
+/// canned replies. It can be used to test devices and custom drivers without any hardware.
+///
+/// Every line that is written is recorded and compared to the next expected command. Lines that
+/// don't match fail with [`Error::UnexpectedCommand`]. Empty lines are always answered with the
+/// current prompt, like a real device does, so the prompt discovery of the devices works without
+/// scripting it.
+///
+/// Every reply is read as a chunk of its own, like a device that answers in separate packets.
+/// Since the replies are all there right after the command was written, a read that finds nothing
+/// left fails right away with [`Error::Timeout`] instead of waiting for the command timeout, e.g.
+/// when the script is missing a prompt.
+///
+/// ```
+/// use rustmiko::devices::cisco::CiscoDevice;
+/// use rustmiko::devices::generic::connection::MockConnection;
+/// use rustmiko::devices::generic::device_types::config::{Configurable, InterfaceConfigurable};
+///
+/// let mock = MockConnection::new("sw01#")
+///     .expect("terminal length 0", "")
+///     .expect("terminal width 511", "")
+///     .expect_prompt("configure terminal", "", "sw01(config)#")
+///     .expect_prompt("interface gi0/1", "", "sw01(config-if)#")
+///     .expect("no shutdown", "")
+///     .expect_prompt("exit", "", "sw01(config)#")
+///     .expect_prompt("end", "", "sw01#");
+///
+/// let mut cisco = CiscoDevice::from_connection(mock)?;
+/// {
+///     let mut config = cisco.enter_config()?;
+///     let interface = config.get_interface("gi", &[0, 1]);
+///     config.interface_up(&interface)?;
+/// }
+///
+/// cisco.connection().assert_done();
+/// assert_eq!(cisco.connection().sent()[3..], ["configure terminal", "interface gi0/1", "no shutdown", "exit", "end"]);
+/// # Ok::<(), rustmiko::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockConnection {
+	prompt: String,
+	expectations: VecDeque<Expectation>,
+	sent: Vec<String>,
+	unexpected: Vec<String>,
+	input: String,
+	output: VecDeque<Vec<u8>>,
+	/// Everything that was answered since the last line was written, for the timeout errors.
+	reply: Vec<u8>,
+	disconnected: bool,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl MockConnection {
+	/// Create a mock that shows the given prompt, e.g. `sw01#`, after every command.
+	pub fn new<S: Into<String>>(prompt: S) -> MockConnection {
+		MockConnection {
+			prompt: prompt.into(),
+			expectations: VecDeque::new(),
+			sent: Vec::new(),
+			unexpected: Vec::new(),
+			input: String::new(),
+			output: VecDeque::new(),
+			reply: Vec::new(),
+			disconnected: false,
+			command_timeout: DEFAULT_MOCK_COMMAND_TIMEOUT,
+			line_ending: "\n".to_string(),
+		}
+	}
+
+	/// Send data right away, before anything was written, like the banner and the first prompt an
+	/// SSH session greets with.
+	pub fn greeting<S: Into<String>>(mut self, greeting: S) -> Self {
+		self.output.push_back(greeting.into().into_bytes());
+		self
+	}
+
+	/// Expect a command and answer it with its echo, the output and the current prompt.
+	pub fn expect<C: Into<String>, O: Into<String>>(self, command: C, output: O) -> Self {
+		self.push(command.into(), Reply::Output {
+			output: output.into(),
+			prompt: None,
+		})
+	}
+
+	/// Expect a command that changes the prompt, e.g. `configure terminal`. The new prompt is used
+	/// for this and all following replies.
+	pub fn expect_prompt<C: Into<String>, O: Into<String>, P: Into<String>>(self, command: C, output: O, prompt: P) -> Self {
+		self.push(command.into(), Reply::Output {
+			output: output.into(),
+			prompt: Some(prompt.into()),
+		})
+	}
+
+	/// Expect a command and answer it with exactly the given data, without an echo or a prompt.
+	/// Useful for password prompts, or to let the command run into a timeout with an empty reply.
+	pub fn expect_raw<C: Into<String>, R: Into<String>>(self, command: C, reply: R) -> Self {
+		self.push(command.into(), Reply::Raw(reply.into()))
+	}
+
+	/// Expect a command after which the device closes the connection.
+	pub fn expect_disconnect<C: Into<String>>(self, command: C) -> Self {
+		self.push(command.into(), Reply::Disconnect)
+	}
+
+	/// Set the command timeout the mock reports. It never waits for it, reads without a reply
+	/// fail right away.
+	pub fn command_timeout(mut self, timeout: Duration) -> Self {
+		self.command_timeout = timeout;
+		self
+	}
+
+	/// Set the line ending that ends a command, `\n` by default.
+	pub fn line_ending<S: Into<String>>(mut self, line_ending: S) -> Self {
+		self.line_ending = line_ending.into();
+		self
+	}
+
+	/// All lines that were written so far, including empty lines and the unexpected ones.
+	pub fn sent(&self) -> &[String] {
+		&self.sent
+	}
+
+	/// The commands that were written, but didn't match the script.
+	pub fn unexpected(&self) -> &[String] {
+		&self.unexpected
+	}
+
+	/// The expected commands that weren't sent yet.
+	pub fn remaining(&self) -> Vec<&str> {
+		self.expectations.iter()
+			.map(|expectation| expectation.command.as_str())
+			.collect()
+	}
+
+	/// Panics if an unexpected command was sent or not all expected commands were sent. Errors of
+	/// unexpected commands may be swallowed by the code under test, so this should be checked at
+	/// the end of every test.
+	pub fn assert_done(&self) {
+		assert!(self.unexpected.is_empty(), "Unexpected commands were sent: {:?}", self.unexpected);
+		assert!(self.expectations.is_empty(), "Expected commands weren't sent: {:?}", self.remaining());
+	}
+
+	fn push(mut self, command: String, reply: Reply) -> Self {
+		self.expectations.push_back(Expectation { command, reply });
+		self
+	}
+
+	/// Answer a complete line that was written.
+	fn handle_line(&mut self, line: String) -> Result<()> {
+		self.sent.push(line.clone());
+		self.reply.clear();
+
+		if line.trim().is_empty() {
+			self.output.push_back(format!("\r\n{}", self.prompt).into_bytes());
+			return Ok(());
+		}
+
+		let reply = match self.expectations.pop_front() {
+			Some(expectation) if expectation.command == line => expectation.reply,
+			other => {
+				let expected = other.map(|expectation| {
+					let command = expectation.command.clone();
+					self.expectations.push_front(expectation);
+					command
+				});
+				self.unexpected.push(line.clone());
+				return Err(Error::UnexpectedCommand { command: line, expected });
+			},
+		};
+		match reply {
+			Reply::Output { output, prompt } => {
+				if let Some(prompt) = prompt {
+					self.prompt = prompt;
+				}
+
+				let mut reply = format!("{}\r\n{}", line, output);
+				if !output.is_empty() && !output.ends_with('\n') {
+					reply.push_str("\r\n");
+				}
+				reply.push_str(&self.prompt);
+				self.output.push_back(reply.into_bytes());
+			},
+			Reply::Raw(reply) => self.output.push_back(reply.into_bytes()),
+			Reply::Disconnect => self.disconnected = true,
+		}
+
+		Ok(())
+	}
+}
+
+impl Connection for MockConnection {
+	type ConnectionHandler = MockConnection;
+
+	/// A mock can't connect anywhere, create it with [`MockConnection::new`] and pass it to the
+	/// device with `from_connection` instead.
+	fn connect_with(_builder: &ConnectionBuilder) -> Result<MockConnection> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "A MockConnection can't connect, use MockConnection::new").into())
+	}
+
+	fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		if self.disconnected {
+			return Err(io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed by the mock").into());
+		}
+
+		self.input.push_str(&String::from_utf8_lossy(data));
+		while let Some(index) = self.input.find(&self.line_ending) {
+			let line = self.input[..index].to_string();
+			self.input.drain(..index + self.line_ending.len());
+			self.handle_line(line)?;
+		}
+
+		Ok(())
+	}
+
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+		if let Some(output) = self.output.pop_front() {
+			self.reply.extend_from_slice(&output);
+			return Ok(Some(output));
+		}
+		if self.disconnected {
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Connection closed by the mock").into());
+		}
+
+		// The whole reply was read already, so waiting for more would only burn the timeout.
+		Err(Error::Timeout {
+			command: self.sent.last().cloned().unwrap_or_default(),
+			output: normalize(&self.reply),
+		})
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use regex::Regex;
+	use crate::devices::generic::connection::{Connection, MockConnection};
+	use crate::error::Error;
+
+	fn prompt() -> Regex {
+		Regex::new(r"^sw01[>#]$").unwrap()
+	}
+
+	#[test]
+	fn answers_with_echo_output_and_prompt() {
+		let mut mock = MockConnection::new("sw01#").expect("show clock", "12:00:00");
+		let (output, prompt) = mock.send_command_with_prompt("show clock", &prompt()).unwrap();
+		assert_eq!(output, "12:00:00");
+		assert_eq!(prompt, "sw01#");
+		mock.assert_done();
+	}
+
+	#[test]
+	fn unexpected_command_fails() {
+		let mut mock = MockConnection::new("sw01#").expect("show clock", "");
+		match mock.send_command("show version", &prompt()) {
+			Err(Error::UnexpectedCommand { command, expected }) => {
+				assert_eq!(command, "show version");
+				assert_eq!(expected.as_deref(), Some("show clock"));
+			},
+			other => panic!("expected UnexpectedCommand, got {:?}", other),
+		}
+		assert_eq!(mock.unexpected(), ["show version"]);
+		assert_eq!(mock.remaining(), ["show clock"]);
+	}
+
+	#[test]
+	fn read_without_reply_fails_right_away() {
+		let mut mock = MockConnection::new("sw01#")
+			.command_timeout(Duration::from_secs(60))
+			.expect_raw("reload", "Proceed with reload? [confirm]");
+
+		let start = Instant::now();
+		match mock.send_command("reload", &prompt()) {
+			Err(Error::Timeout { command, output }) => {
+				assert_eq!(command, "reload");
+				assert_eq!(output, "Proceed with reload? [confirm]");
+			},
+			other => panic!("expected Timeout, got {:?}", other),
+		}
+		assert!(start.elapsed() < Duration::from_secs(1));
+	}
+
+	#[test]
+	fn disconnect_closes_the_connection() {
+		let mut mock = MockConnection::new("sw01#").expect_disconnect("exit");
+		assert!(matches!(mock.send_command("exit", &prompt()), Err(Error::Io(_))));
+		assert!(matches!(mock.write_raw(b"show clock\n"), Err(Error::Io(_))));
+	}
+}
@@ -0,0 +1,321 @@
+#[cfg(feature = "async")]
+mod async_connection;
+#[cfg(feature = "async")]
+mod async_ssh;
+#[cfg(feature = "async")]
+mod async_telnet;
+mod auth;
+mod buffer;
+mod builder;
+mod host_key;
+mod mock;
+mod normalize;
+mod replay;
+mod ssh;
+mod telnet;
+mod tunnel;
+
+use std::net::ToSocketAddrs;
+use std::time::{Duration, Instant};
+use log::debug;
+use regex::Regex;
+use crate::error::{Error, Result};
+
+#[cfg(feature = "async")]
+pub use async_connection::AsyncConnection;
+#[cfg(feature = "async")]
+pub use async_ssh::AsyncSSHConnection;
+#[cfg(feature = "async")]
+pub use async_telnet::AsyncTelnetConnection;
+pub use auth::{AuthMethod, KeyboardInteractiveHandler};
+pub use ssh2::Prompt as AuthPrompt;
+pub(crate) use buffer::PromptBuffer;
+pub use builder::{ConnectionBuilder, PrivateKey, DEFAULT_COMMAND_TIMEOUT, DEFAULT_CONNECT_TIMEOUT, DEFAULT_READ_TIMEOUT, DEFAULT_SSH_PORT, DEFAULT_TELNET_PORT};
+pub use host_key::{host_key_fingerprint, HostKeyPolicy};
+pub use mock::MockConnection;
+pub use normalize::normalize;
+pub use replay::{RecordedEvent, Recording, RecordingConnection, ReplayConnection};
+pub use ssh::SSHConnection;
+pub use telnet::TelnetConnection;
+
+/// A Connection trait describes a basic set of functions that are necessary for the most basic of
+/// implementations.
+///
+/// Connecting, reading and writing specifically need to be implemented.
+pub trait Connection {
+	type ConnectionHandler;
+
+	/// Connects to the device with all the settings of a [`ConnectionBuilder`].
+	fn connect_with(builder: &ConnectionBuilder) -> Result<Self::ConnectionHandler>;
+	/// Connects to the specified address using a Connection Handler.
+	fn connect<A: ToSocketAddrs>(addr: A, username: Option<&str>, password: Option<&str>) -> Result<Self::ConnectionHandler> {
+		let mut builder = ConnectionBuilder::from_addr(addr)?;
+		if let Some(username) = username {
+			builder = builder.username(username);
+		}
+		if let Some(password) = password {
+			builder = builder.password(password);
+		}
+		Self::connect_with(&builder)
+	}
+	/// Writes raw bytes to the connection without waiting for any response.
+	fn write_raw(&mut self, data: &[u8]) -> Result<()>;
+	/// Reads the next chunk of data sent by the server. Returns `None` if nothing arrived within
+	/// the read timeout.
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>>;
+
+	/// How long to wait for the prompt after a command was sent.
+	fn command_timeout(&self) -> Duration {
+		DEFAULT_COMMAND_TIMEOUT
+	}
+
+	/// The line ending appended to every command.
+	fn line_ending(&self) -> &str {
+		"\n"
+	}
+
+	/// Reads input sent by the server until the prompt is found and returns everything that was
+	/// read, including the prompt itself. The output is [`normalize`]d, so escape sequences,
+	/// backspaces and redraws are resolved and lines end with `\n`.
+	///
+	/// The output is buffered, so the prompt is only matched at the very end of everything that
+	/// was received so far, even if it was split over multiple reads. If the prompt doesn't show up
+	/// within the command timeout, a [`Error::Timeout`] containing the partial output is returned.
+	///
+	/// If the output stops at a pager like `--More--` or `---(more)---`, the pager is removed from
+	/// the output and a space is sent to get the rest of it.
+	fn read_until_prompt(&mut self, prompt_end: &Regex) -> Result<String> {
+		self.read_until_prompt_raw(prompt_end).map(|output| normalize(&output))
+	}
+
+	/// Like [`Connection::read_until_prompt`], but returns the bytes exactly as they were
+	/// received, only without the pagers.
+	fn read_until_prompt_raw(&mut self, prompt_end: &Regex) -> Result<Vec<u8>> {
+		let mut buffer = PromptBuffer::new(prompt_end)?;
+		let deadline = Instant::now() + self.command_timeout();
+
+		while Instant::now() < deadline {
+			let Some(data) = self.read_chunk()? else {
+				continue;
+			};
+
+			debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+			if buffer.push(&data) {
+				debug!("Found prompt. Ready for next command");
+				return Ok(buffer.into_raw());
+			}
+			if buffer.take_pager() {
+				debug!("Output stopped at the pager. Sending a space");
+				self.write_raw(b" ")?;
+			}
+		}
+
+		Err(Error::Timeout {
+			command: String::new(),
+			output: buffer.into_string(),
+		})
+	}
+
+	/// Reads input sent by the server until the pattern matches anywhere in the output and returns
+	/// everything that was read, [`normalize`]d. Unlike [`Connection::read_until_prompt`], the
+	/// pattern doesn't have to be on the last line, which is needed for lines like
+	/// `[Type ^D at a new line to end input]` that are followed by a line break. Use `(?m)` in the
+	/// pattern to match single lines with `^` and `$`.
+	fn read_until_pattern(&mut self, pattern: &Regex) -> Result<String> {
+		let mut buffer = PromptBuffer::new(pattern)?;
+		let deadline = Instant::now() + self.command_timeout();
+
+		while Instant::now() < deadline {
+			let Some(data) = self.read_chunk()? else {
+				continue;
+			};
+
+			debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+			if buffer.push(&data) || buffer.contains(pattern) {
+				debug!("Found pattern");
+				return Ok(buffer.into_string());
+			}
+			if buffer.take_pager() {
+				debug!("Output stopped at the pager. Sending a space");
+				self.write_raw(b" ")?;
+			}
+		}
+
+		Err(Error::Timeout {
+			command: String::new(),
+			output: buffer.into_string(),
+		})
+	}
+
+	/// Reads everything the server still sends until nothing arrives within the read timeout, and
+	/// returns it [`normalize`]d. A read that fails with [`Error::Timeout`], like the one of a
+	/// [`MockConnection`] with nothing left to answer, counts as quiet as well.
+	fn read_until_quiet(&mut self) -> Result<String> {
+		let mut output = Vec::new();
+		let deadline = Instant::now() + self.command_timeout();
+
+		while Instant::now() < deadline {
+			match self.read_chunk() {
+				Ok(Some(data)) => {
+					debug!("Read \"{}\"", String::from_utf8_lossy(&data));
+					output.extend_from_slice(&data);
+				},
+				Ok(None) | Err(Error::Timeout { .. }) => break,
+				Err(e) => return Err(e),
+			}
+		}
+
+		Ok(normalize(&output))
+	}
+
+	/// Sends an empty line and returns the prompt the device answers with, using a loose
+	/// `prompt_end` pattern to know when to stop reading.
+	///
+	/// This is used by devices to discover their exact prompt after logging in. The greeting of
+	/// a session, like the banner and the first prompt after an SSH shell was opened, may arrive
+	/// before the answer to the empty line and end the read early. So everything that follows is
+	/// read until the connection goes quiet, otherwise the answer would be taken for the output of
+	/// the next command.
+	fn find_prompt(&mut self, prompt_end: &Regex) -> Result<String> {
+		self.write_raw(self.line_ending().to_string().as_bytes())?;
+		let mut output = self.read_until_prompt(prompt_end)?;
+		output.push_str(&self.read_until_quiet()?);
+
+		let prompt = last_line(&output).map(String::from);
+
+		prompt.ok_or_else(|| Error::PromptMismatch {
+			expected: prompt_end.to_string(),
+			output,
+		})
+	}
+
+	/// Reads input, sent by the server but ignores it.
+	fn read_ignore(&mut self, prompt_end: &Regex) {
+		if let Err(e) = self.read_until_prompt(prompt_end) {
+			debug!("Error discarded: {}", e);
+		}
+	}
+
+	/// Executes a raw string command on the connection.
+	fn execute_raw(&mut self, command: &str, prompt_end: &Regex) -> Result<()> {
+		self.send_command(command, prompt_end)?;
+		Ok(())
+	}
+
+	/// Sends a command and returns the output the device answered with. The echoed command and
+	/// the trailing prompt are removed from the output. The line ending is automatically appended.
+	fn send_command(&mut self, command: &str, prompt_end: &Regex) -> Result<String> {
+		let (output, _) = self.send_command_with_prompt(command, prompt_end)?;
+		Ok(output)
+	}
+
+	/// Like [`Connection::send_command`], but also returns the prompt the device answered with,
+	/// e.g. to track the mode the device is in.
+	fn send_command_with_prompt(&mut self, command: &str, prompt_end: &Regex) -> Result<(String, String)> {
+		debug!("Wrote: {}", command);
+
+		let line = format!("{}{}", command, self.line_ending());
+		self.write_raw(line.as_bytes())?;
+		let output = self.read_until_prompt(prompt_end)
+			.map_err(|e| e.with_command(command))?;
+
+		let prompt = last_line(&output).unwrap_or_default().to_string();
+		Ok((strip_command_and_prompt(&output, command, prompt_end), prompt))
+	}
+
+	/// Sends a command and returns everything the device answered with as it was received,
+	/// including the echoed command and the prompt.
+	fn send_command_raw(&mut self, command: &str, prompt_end: &Regex) -> Result<Vec<u8>> {
+		debug!("Wrote: {}", command);
+
+		let line = format!("{}{}", command, self.line_ending());
+		self.write_raw(line.as_bytes())?;
+		self.read_until_prompt_raw(prompt_end)
+			.map_err(|e| e.with_command(command))
+	}
+}
+
+/// The last line of the output that isn't empty, which is usually the prompt.
+pub(crate) fn last_line(output: &str) -> Option<&str> {
+	output.lines()
+		.rev()
+		.map(str::trim)
+		.find(|line| !line.is_empty())
+}
+
+/// Cleans up the raw output of a command by removing the echoed command from the first line and
+/// the prompt from the last line. Line endings are normalized to `\n`.
+pub(crate) fn strip_command_and_prompt(output: &str, command: &str, prompt_end: &Regex) -> String {
+	let mut lines: Vec<&str> = output
+		.split('\n')
+		.map(|line| line.trim_end_matches('\r'))
+		.collect();
+
+	let command = command.trim();
+	if !command.is_empty() && lines.first().is_some_and(|line| line.trim_end().ends_with(command)) {
+		lines.remove(0);
+	}
+
+	while lines.last().is_some_and(|line| line.trim().is_empty()) {
+		lines.pop();
+	}
+	if lines.last().is_some_and(|line| prompt_end.is_match(line.trim_end())) {
+		lines.pop();
+	}
+	while lines.last().is_some_and(|line| line.trim().is_empty()) {
+		lines.pop();
+	}
+
+	lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use regex::Regex;
+	use super::{last_line, strip_command_and_prompt, Connection, MockConnection};
+
+	fn prompt() -> Regex {
+		Regex::new(r"^sw01(\([^)]*\))?[>#]$").unwrap()
+	}
+
+	#[test]
+	fn strips_the_echo_and_the_prompt() {
+		let output = "show clock\n*12:00:00.000 UTC Mon May 1 2024\nsw01#";
+		assert_eq!(strip_command_and_prompt(output, "show clock", &prompt()), "*12:00:00.000 UTC Mon May 1 2024");
+	}
+
+	#[test]
+	fn strips_an_echo_behind_the_previous_prompt() {
+		let output = "sw01#show version | include uptime\r\nsw01 uptime is 1 week\r\n\r\nsw01#";
+		assert_eq!(strip_command_and_prompt(output, "show version | include uptime", &prompt()), "sw01 uptime is 1 week");
+	}
+
+	#[test]
+	fn keeps_the_output_without_an_echo() {
+		let output = "  show clock\n  show version\nsw01(config)#\n";
+		assert_eq!(strip_command_and_prompt(output, "show history", &prompt()), "  show clock\n  show version");
+	}
+
+	#[test]
+	fn empty_output() {
+		assert_eq!(strip_command_and_prompt("interface gi0/1\nsw01(config-if)#", "interface gi0/1", &prompt()), "");
+		assert_eq!(strip_command_and_prompt("", "", &prompt()), "");
+	}
+
+	#[test]
+	fn find_prompt_reads_the_answer_behind_the_greeting() {
+		let mut mock = MockConnection::new("sw01>")
+			.greeting("\r\nUnauthorized access is prohibited\r\n\r\nsw01>")
+			.expect("show clock", "*12:00:00.000 UTC Mon May 1 2024");
+
+		assert_eq!(mock.find_prompt(&Regex::new("[>#]").unwrap()).unwrap(), "sw01>");
+		assert_eq!(mock.send_command("show clock", &prompt()).unwrap(), "*12:00:00.000 UTC Mon May 1 2024");
+		mock.assert_done();
+	}
+
+	#[test]
+	fn last_line_skips_blank_lines() {
+		assert_eq!(last_line("show clock\nsw01# \n\n"), Some("sw01#"));
+		assert_eq!(last_line("\n \n"), None);
+	}
+}
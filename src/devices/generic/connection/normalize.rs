@@ -0,0 +1,127 @@
+/// Cleans up raw terminal output the way a terminal would display it.
+///
+/// ANSI escape sequences like colors or cursor movements are removed, backspaces move back over
+/// the line and carriage returns go back to the start of it, so redrawn text like an erased pager
+/// ends up as it was shown on the screen. Line endings are normalized to `\n` and other control
+/// characters are dropped.
+///
+/// ```
+/// use rustmiko::devices::generic::connection::normalize;
+///
+/// assert_eq!(normalize(b"\x1b[1mshow\x1b[0m version\r\n"), "show version\n");
+/// assert_eq!(normalize(b"--More--\x08\x08\x08\x08\x08\x08\x08\x08        \x08\x08\x08\x08\x08\x08\x08\x08end"), "end");
+/// ```
+pub fn normalize(raw: &[u8]) -> String {
+	let text = String::from_utf8_lossy(raw);
+	let mut chars = text.chars().peekable();
+
+	let mut output = String::with_capacity(text.len());
+	let mut line = LineBuffer::default();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'\x1b' => match chars.next() {
+				// Control sequence: parameters and intermediates up to a final byte in `@` to `~`.
+				Some('[') => {
+					for c in chars.by_ref() {
+						if ('@'..='~').contains(&c) {
+							break;
+						}
+					}
+				},
+				// Operating system command: terminated by BEL or `ESC \`.
+				Some(']') => {
+					while let Some(c) = chars.next() {
+						if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+							break;
+						}
+					}
+				},
+				// Character set selection, which has one more byte.
+				Some('(' | ')' | '*' | '+') => {
+					chars.next();
+				},
+				_ => {},
+			},
+			'\n' => {
+				line.finish(&mut output);
+				output.push('\n');
+			},
+			'\r' => line.cursor = 0,
+			'\x08' => line.cursor = line.cursor.saturating_sub(1),
+			'\t' => line.write(c),
+			c if c.is_control() => {},
+			c => line.write(c),
+		}
+	}
+
+	line.finish(&mut output);
+	output
+}
+
+/// The line the cursor is on while normalizing.
+#[derive(Default)]
+struct LineBuffer {
+	chars: Vec<char>,
+	cursor: usize,
+	/// Whether anything on the line was overwritten. Redrawn lines are often padded with spaces
+	/// to erase what was there before, which are trimmed.
+	redrawn: bool,
+}
+
+impl LineBuffer {
+	fn write(&mut self, c: char) {
+		match self.chars.get_mut(self.cursor) {
+			Some(existing) => {
+				*existing = c;
+				self.redrawn = true;
+			},
+			None => self.chars.push(c),
+		}
+		self.cursor += 1;
+	}
+
+	fn finish(&mut self, output: &mut String) {
+		let line: String = self.chars.drain(..).collect();
+		match self.redrawn {
+			true => output.push_str(line.trim_end()),
+			false => output.push_str(&line),
+		}
+		self.cursor = 0;
+		self.redrawn = false;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::normalize;
+
+	#[test]
+	fn removes_ansi_sequences() {
+		assert_eq!(normalize(b"\x1b[0;32mup\x1b[0m\r\n\x1b[Ksw01#"), "up\nsw01#");
+		assert_eq!(normalize(b"\x1b]0;admin@srx: cli\x07admin@srx> "), "admin@srx> ");
+		assert_eq!(normalize(b"\x1b]0;title\x1b\\\x1b(Bsw01#"), "sw01#");
+	}
+
+	#[test]
+	fn backspaces_overwrite_the_line() {
+		// The echo of `shw`, two erased characters and `ow`.
+		assert_eq!(normalize(b"shw\x08 \x08\x08 \x08how\r\n"), "show\n");
+		// The IOS pager is erased with backspaces, spaces and backspaces again.
+		assert_eq!(normalize(b" --More-- \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08          \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08 description uplink\r\n"), " description uplink\n");
+	}
+
+	#[test]
+	fn carriage_returns_redraw_the_line() {
+		// The Junos pager is erased by going back to the start of the line.
+		assert_eq!(normalize(b"---(more 42%)---\r                \r    ge-0/0/1 {\r\n"), "    ge-0/0/1 {\n");
+		assert_eq!(normalize(b"Building configuration...\r\n\r\r\nend"), "Building configuration...\n\nend");
+	}
+
+	#[test]
+	fn drops_other_control_characters() {
+		assert_eq!(normalize(b"\x07\x00Password:\x07 "), "Password: ");
+		assert_eq!(normalize(b"a\tb"), "a\tb");
+		assert_eq!(normalize(b""), "");
+	}
+}
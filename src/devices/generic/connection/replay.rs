@@ -0,0 +1,503 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::devices::generic::connection::{normalize, Connection, ConnectionBuilder, DEFAULT_COMMAND_TIMEOUT};
+use crate::error::{Error, Result};
+
+/// The first line of every recording file.
+const RECORDING_HEADER: &str = "rustmiko-recording 1";
+
+/// A single thing that happened on a recorded connection, with the time since the recording
+/// started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedEvent {
+	/// Data that was written to the device.
+	Write {
+		at: Duration,
+		data: Vec<u8>,
+	},
+	/// A chunk of data that was read from the device.
+	Read {
+		at: Duration,
+		data: Vec<u8>,
+	},
+	/// A read that didn't return anything within the read timeout.
+	Timeout {
+		at: Duration,
+	},
+}
+
+/// A Recording holds everything that was sent and received on a connection, exactly in the chunks
+/// it was read in, so the prompt handling can be reproduced later with a [`ReplayConnection`].
+///
+/// It's saved as a text file, one event per line, with the milliseconds since the start of the
+/// recording, the kind of the event and the escaped data:
+///
+/// ```text
+/// rustmiko-recording 1
+/// line-ending \n
+/// command-timeout 60000
+/// 0 write \n
+/// 12 read \r\nsw01#
+/// 15 write show clock\n
+/// 40 read show clock\r\n*12:00:00.000 UTC Mon Mar 1 1993\r\nsw01#
+/// ```
+///
+/// Recordings contain everything that was written after the login, including passwords sent to
+/// commands like `enable`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+	line_ending: String,
+	command_timeout: Duration,
+	events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+	/// Create an empty recording of a connection using the given line ending and command timeout.
+	pub fn new<S: Into<String>>(line_ending: S, command_timeout: Duration) -> Recording {
+		Recording {
+			line_ending: line_ending.into(),
+			command_timeout,
+			events: Vec::new(),
+		}
+	}
+
+	/// Load a recording from a file written by [`Recording::save`].
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<Recording> {
+		Self::parse(&fs::read_to_string(path)?)
+	}
+
+	/// Parse a recording from the text format.
+	pub fn parse(text: &str) -> Result<Recording> {
+		let mut lines = text.lines();
+		if lines.next() != Some(RECORDING_HEADER) {
+			return Err(invalid_data("Not a rustmiko recording"));
+		}
+
+		let mut recording = Recording::new("\n", DEFAULT_COMMAND_TIMEOUT);
+		for line in lines.filter(|line| !line.is_empty()) {
+			let mut parts = line.splitn(3, ' ');
+			let (first, second, data) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+			match first {
+				"line-ending" => recording.line_ending = String::from_utf8_lossy(&unescape(second)?).into_owned(),
+				"command-timeout" => recording.command_timeout = Duration::from_millis(parse_millis(second)?),
+				at => {
+					let at = Duration::from_millis(parse_millis(at)?);
+					let event = match second {
+						"write" => RecordedEvent::Write { at, data: unescape(data)? },
+						"read" => RecordedEvent::Read { at, data: unescape(data)? },
+						"timeout" => RecordedEvent::Timeout { at },
+						kind => return Err(invalid_data(&format!("Unknown event `{}`", kind))),
+					};
+					recording.events.push(event);
+				},
+			}
+		}
+
+		Ok(recording)
+	}
+
+	/// Save the recording to a file in the text format.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+		fs::write(path, self.to_text())?;
+		Ok(())
+	}
+
+	/// Format the recording in the text format.
+	pub fn to_text(&self) -> String {
+		let mut text = format!("{}\n", RECORDING_HEADER);
+		let _ = writeln!(text, "line-ending {}", escape(self.line_ending.as_bytes()));
+		let _ = writeln!(text, "command-timeout {}", self.command_timeout.as_millis());
+
+		for event in &self.events {
+			let _ = match event {
+				RecordedEvent::Write { at, data } => writeln!(text, "{} write {}", at.as_millis(), escape(data)),
+				RecordedEvent::Read { at, data } => writeln!(text, "{} read {}", at.as_millis(), escape(data)),
+				RecordedEvent::Timeout { at } => writeln!(text, "{} timeout", at.as_millis()),
+			};
+		}
+
+		text
+	}
+
+	/// All events in the order they happened.
+	pub fn events(&self) -> &[RecordedEvent] {
+		&self.events
+	}
+
+	/// Add an event to the end of the recording.
+	pub fn push(&mut self, event: RecordedEvent) {
+		self.events.push(event);
+	}
+
+	/// The line ending the recorded connection used.
+	pub fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+
+	/// The command timeout the recorded connection used.
+	pub fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+}
+
+/// A RecordingConnection wraps another Connection and records everything that is written and read
+/// after the login. Use it like any other connection type, e.g.
+/// `CiscoDevice<RecordingConnection<TelnetConnection>>`, and save the recording when done.
+///
+/// ```ignore
+/// let builder = ConnectionBuilder::new("192.168.1.101").username("admin").password("admin");
+/// let mut cisco = CiscoDevice::<RecordingConnection<TelnetConnection>>::connect_with(&builder)?;
+/// cisco.send_command("show version")?;
+/// cisco.connection().recording().save("show_version.rec")?;
+/// ```
+pub struct RecordingConnection<C> {
+	inner: C,
+	started: Instant,
+	recording: Recording,
+}
+
+impl<C: Connection> RecordingConnection<C> {
+	/// Start recording an already established connection.
+	pub fn new(inner: C) -> RecordingConnection<C> {
+		let recording = Recording::new(inner.line_ending(), inner.command_timeout());
+		RecordingConnection {
+			inner,
+			started: Instant::now(),
+			recording,
+		}
+	}
+}
+
+impl<C> RecordingConnection<C> {
+	/// Everything that was recorded so far.
+	pub fn recording(&self) -> &Recording {
+		&self.recording
+	}
+
+	/// The wrapped connection.
+	pub fn inner(&self) -> &C {
+		&self.inner
+	}
+
+	/// Stop recording and return the wrapped connection along with the recording.
+	pub fn into_parts(self) -> (C, Recording) {
+		(self.inner, self.recording)
+	}
+}
+
+impl<C: Connection<ConnectionHandler = C>> Connection for RecordingConnection<C> {
+	type ConnectionHandler = RecordingConnection<C>;
+
+	/// Connect with the wrapped connection type and start recording once the login is done.
+	fn connect_with(builder: &ConnectionBuilder) -> Result<RecordingConnection<C>> {
+		Ok(RecordingConnection::new(C::connect_with(builder)?))
+	}
+
+	fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		self.recording.push(RecordedEvent::Write {
+			at: self.started.elapsed(),
+			data: data.to_vec(),
+		});
+		self.inner.write_raw(data)
+	}
+
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+		let chunk = self.inner.read_chunk()?;
+		let at = self.started.elapsed();
+		self.recording.push(match &chunk {
+			Some(data) => RecordedEvent::Read { at, data: data.clone() },
+			None => RecordedEvent::Timeout { at },
+		});
+		Ok(chunk)
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.inner.command_timeout()
+	}
+
+	fn line_ending(&self) -> &str {
+		self.inner.line_ending()
+	}
+}
+
+/// A ReplayConnection plays back a [`Recording`] as if it was the device. Everything that is
+/// written has to match the recorded writes exactly, otherwise [`Error::UnexpectedCommand`] is
+/// returned. Reads return the recorded chunks in the same order and sizes as they were received.
+///
+/// By default the replay runs as fast as possible, [`ReplayConnection::realtime`] waits for the
+/// recorded timing instead. A read while the recording continues with a write fails right away
+/// with [`Error::Timeout`], since the device didn't send anything more at that point.
+///
+/// ```ignore
+/// let replay = ReplayConnection::load("show_version.rec")?;
+/// let mut cisco = CiscoDevice::from_connection(replay)?;
+/// assert!(cisco.send_command("show version")?.contains("IOS"));
+/// ```
+pub struct ReplayConnection {
+	events: VecDeque<RecordedEvent>,
+	/// The data written last and everything read since, for the timeout errors.
+	last_write: Vec<u8>,
+	reply: Vec<u8>,
+	started: Instant,
+	realtime: bool,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl ReplayConnection {
+	/// Replay a recording.
+	pub fn new(recording: Recording) -> ReplayConnection {
+		ReplayConnection {
+			last_write: Vec::new(),
+			reply: Vec::new(),
+			started: Instant::now(),
+			realtime: false,
+			command_timeout: recording.command_timeout,
+			line_ending: recording.line_ending,
+			events: recording.events.into(),
+		}
+	}
+
+	/// Replay a recording from a file.
+	pub fn load<P: AsRef<Path>>(path: P) -> Result<ReplayConnection> {
+		Ok(Self::new(Recording::load(path)?))
+	}
+
+	/// Wait for the recorded time before every read, to reproduce timing related issues.
+	pub fn realtime(mut self, realtime: bool) -> Self {
+		self.realtime = realtime;
+		self
+	}
+
+	/// Change how long to wait for the prompt, instead of the recorded command timeout.
+	pub fn set_command_timeout(&mut self, timeout: Duration) {
+		self.command_timeout = timeout;
+	}
+
+	/// The events that weren't replayed yet.
+	pub fn remaining(&self) -> usize {
+		self.events.len()
+	}
+
+	fn wait_until(&self, at: Duration) {
+		if self.realtime {
+			if let Some(delay) = at.checked_sub(self.started.elapsed()) {
+				thread::sleep(delay);
+			}
+		}
+	}
+}
+
+impl Connection for ReplayConnection {
+	type ConnectionHandler = ReplayConnection;
+
+	/// A replay can't connect anywhere, create it from a recording with [`ReplayConnection::load`]
+	/// and pass it to the device with `from_connection` instead.
+	fn connect_with(_builder: &ConnectionBuilder) -> Result<ReplayConnection> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "A ReplayConnection can't connect, use ReplayConnection::load").into())
+	}
+
+	fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		let expected = match self.events.front() {
+			Some(RecordedEvent::Write { data: expected, .. }) if expected == data => {
+				self.events.pop_front();
+				self.last_write = data.to_vec();
+				self.reply.clear();
+				return Ok(());
+			},
+			Some(RecordedEvent::Write { data: expected, .. }) => Some(String::from_utf8_lossy(expected).into_owned()),
+			_ => None,
+		};
+
+		Err(Error::UnexpectedCommand {
+			command: String::from_utf8_lossy(data).into_owned(),
+			expected,
+		})
+	}
+
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+		match self.events.front().cloned() {
+			Some(RecordedEvent::Read { at, data }) => {
+				self.wait_until(at);
+				self.events.pop_front();
+				self.reply.extend_from_slice(&data);
+				Ok(Some(data))
+			},
+			Some(RecordedEvent::Timeout { at }) => {
+				self.wait_until(at);
+				self.events.pop_front();
+				Ok(None)
+			},
+			// The device is waiting for the next write, so nothing more will arrive.
+			Some(RecordedEvent::Write { .. }) => Err(Error::Timeout {
+				command: String::from_utf8_lossy(&self.last_write).trim_end().to_string(),
+				output: normalize(&self.reply),
+			}),
+			None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The recording ended").into()),
+		}
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+}
+
+fn invalid_data(message: &str) -> Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.to_string()).into()
+}
+
+fn parse_millis(text: &str) -> Result<u64> {
+	text.parse().map_err(|_| invalid_data(&format!("Invalid milliseconds `{}`", text)))
+}
+
+/// Escapes data so it fits on a single line. Printable ASCII is kept as is, everything else is
+/// written as `\r`, `\n`, `\t`, `\\` or `\xHH`.
+fn escape(data: &[u8]) -> String {
+	let mut escaped = String::with_capacity(data.len());
+	for &byte in data {
+		match byte {
+			b'\r' => escaped.push_str("\\r"),
+			b'\n' => escaped.push_str("\\n"),
+			b'\t' => escaped.push_str("\\t"),
+			b'\\' => escaped.push_str("\\\\"),
+			0x20..=0x7e => escaped.push(byte as char),
+			_ => {
+				let _ = write!(escaped, "\\x{:02x}", byte);
+			},
+		}
+	}
+	escaped
+}
+
+/// Reverses [`escape`].
+fn unescape(text: &str) -> Result<Vec<u8>> {
+	let mut data = Vec::with_capacity(text.len());
+	let mut bytes = text.bytes();
+
+	while let Some(byte) = bytes.next() {
+		if byte != b'\\' {
+			data.push(byte);
+			continue;
+		}
+
+		match bytes.next() {
+			Some(b'r') => data.push(b'\r'),
+			Some(b'n') => data.push(b'\n'),
+			Some(b't') => data.push(b'\t'),
+			Some(b'\\') => data.push(b'\\'),
+			Some(b'x') => {
+				let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+				let value = Some(hex).filter(|hex| hex.len() == 2)
+					.and_then(|hex| String::from_utf8(hex).ok())
+					.and_then(|hex| u8::from_str_radix(&hex, 16).ok())
+					.ok_or_else(|| invalid_data(&format!("Invalid escape in `{}`", text)))?;
+				data.push(value);
+			},
+			_ => return Err(invalid_data(&format!("Invalid escape in `{}`", text))),
+		}
+	}
+
+	Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use regex::Regex;
+	use crate::devices::cisco::CiscoDevice;
+	use crate::devices::generic::connection::{Connection, MockConnection, RecordedEvent, Recording, RecordingConnection, ReplayConnection};
+	use crate::devices::generic::device_types::config::Configurable;
+	use crate::error::Error;
+	use super::{escape, unescape};
+
+	#[test]
+	fn escape_round_trips_all_bytes() {
+		let data: Vec<u8> = (0..=255).collect();
+		let escaped = escape(&data);
+		assert!(!escaped.contains('\n') && !escaped.contains('\r'));
+		assert_eq!(unescape(&escaped).unwrap(), data);
+
+		assert_eq!(escape(b"a\\b\r\n\t\x1b[K"), "a\\\\b\\r\\n\\t\\x1b[K");
+		assert!(unescape("\\q").is_err());
+		assert!(unescape("\\x4").is_err());
+	}
+
+	#[test]
+	fn save_load_replay_round_trip() {
+		let mock = MockConnection::new("sw01#")
+			.expect("terminal length 0", "")
+			.expect("terminal width 511", "")
+			.expect("show clock", "*12:00:00.000 UTC Mon Mar 1 1993");
+		let mut cisco = CiscoDevice::from_connection(RecordingConnection::new(mock)).unwrap();
+		let clock = cisco.send_command("show clock").unwrap();
+		let recording = cisco.connection().recording().clone();
+
+		let path = std::env::temp_dir().join(format!("rustmiko-replay-{}.rec", std::process::id()));
+		recording.save(&path).unwrap();
+		let loaded = Recording::load(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		// The times are saved in whole milliseconds, everything else has to survive as it is.
+		assert_eq!(loaded.to_text(), recording.to_text());
+		assert_eq!(Recording::parse(&loaded.to_text()).unwrap(), loaded);
+
+		let mut replayed = CiscoDevice::from_connection(ReplayConnection::new(loaded)).unwrap();
+		assert_eq!(replayed.send_command("show clock").unwrap(), clock);
+		assert_eq!(replayed.connection().remaining(), 0);
+	}
+
+	#[test]
+	fn parses_the_text_format() {
+		let recording = Recording::parse("rustmiko-recording 1\nline-ending \\r\ncommand-timeout 500\n0 write \\r\n3 timeout\n12 read \\r\\nsw01# \n").unwrap();
+		assert_eq!(recording.line_ending(), "\r");
+		assert_eq!(recording.command_timeout(), Duration::from_millis(500));
+		assert_eq!(recording.events(), [
+			RecordedEvent::Write { at: Duration::ZERO, data: b"\r".to_vec() },
+			RecordedEvent::Timeout { at: Duration::from_millis(3) },
+			RecordedEvent::Read { at: Duration::from_millis(12), data: b"\r\nsw01# ".to_vec() },
+		]);
+
+		assert!(Recording::parse("something else\n").is_err());
+		assert!(Recording::parse("rustmiko-recording 1\n0 jump \n").is_err());
+	}
+
+	#[test]
+	fn read_before_the_next_write_fails_right_away() {
+		let mut recording = Recording::new("\n", Duration::from_secs(60));
+		recording.push(RecordedEvent::Write { at: Duration::ZERO, data: b"show clock\n".to_vec() });
+		recording.push(RecordedEvent::Read { at: Duration::ZERO, data: b"show clock\r\n12:00".to_vec() });
+		recording.push(RecordedEvent::Write { at: Duration::ZERO, data: b"show version\n".to_vec() });
+		let mut replay = ReplayConnection::new(recording);
+
+		let start = Instant::now();
+		match replay.send_command("show clock", &Regex::new(r"^sw01#$").unwrap()) {
+			Err(Error::Timeout { command, output }) => {
+				assert_eq!(command, "show clock");
+				assert_eq!(output, "show clock\n12:00");
+			},
+			other => panic!("expected Timeout, got {:?}", other),
+		}
+		assert!(start.elapsed() < Duration::from_secs(1));
+	}
+
+	#[test]
+	fn write_that_differs_from_the_recording_fails() {
+		let mut recording = Recording::new("\n", Duration::from_secs(1));
+		recording.push(RecordedEvent::Write { at: Duration::ZERO, data: b"show clock\n".to_vec() });
+		let mut replay = ReplayConnection::new(recording);
+
+		match replay.write_raw(b"show version\n") {
+			Err(Error::UnexpectedCommand { expected, .. }) => assert_eq!(expected.as_deref(), Some("show clock\n")),
+			other => panic!("expected UnexpectedCommand, got {:?}", other),
+		}
+	}
+}
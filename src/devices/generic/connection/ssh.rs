@@ -0,0 +1,131 @@
+use std::io;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use log::debug;
+use ssh2::{Channel, Session};
+use crate::devices::generic::connection::{auth, host_key, AuthMethod, Connection, ConnectionBuilder, DEFAULT_SSH_PORT};
+use crate::error::Result;
+
+pub struct SSHConnection {
+	sess: Session,
+	channel: Channel,
+	auth_method: AuthMethod,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl SSHConnection {
+	/// Connect and verify the host key. Also returns a handle to the socket of the session.
+	fn establish_connection(builder: &ConnectionBuilder) -> Result<(Session, TcpStream)> {
+		let (tcp, host, port) = builder.connect_tcp(DEFAULT_SSH_PORT)?;
+		let socket = tcp.try_clone()?;
+
+		let mut sess = Session::new()?;
+		sess.set_timeout(builder.connect_timeout.as_millis() as u32);
+
+		sess.set_tcp_stream(tcp);
+		sess.handshake()?;
+		host_key::verify_host_key(&sess, &host, port, builder)?;
+
+		Ok((sess, socket))
+	}
+
+	/// Open an authenticated session without a shell, e.g. to a jump host.
+	pub(crate) fn open_session(builder: &ConnectionBuilder) -> Result<(Session, AuthMethod, TcpStream)> {
+		let (sess, socket) = Self::establish_connection(builder)?;
+		let auth_method = auth::authenticate(&sess, builder)?;
+		Ok((sess, auth_method, socket))
+	}
+
+	/// Request a pseudo terminal and start the shell on a new channel of an authenticated session.
+	pub(crate) fn open_shell(session: &Session, builder: &ConnectionBuilder) -> Result<Channel> {
+		let mut channel = session.channel_session()?;
+		let dim = builder.pty_size.map(|(width, height)| (width, height, 0, 0));
+		channel.request_pty(&builder.pty_type, None, dim)?;
+		channel.shell()?;
+		Ok(channel)
+	}
+
+	pub fn connect_agentauth<A: ToSocketAddrs>(addr: A, username: &str, timeout: Option<Duration>) -> Result<SSHConnection> {
+		let mut builder = ConnectionBuilder::from_addr(addr)?
+			.username(username)
+			.auth_methods(&[AuthMethod::Agent]);
+		if let Some(timeout) = timeout {
+			builder = builder.connect_timeout(timeout);
+		}
+		Self::connect_with(&builder)
+	}
+
+	/// The SHA256 fingerprint of the host key of the server, e.g. to pin it for the next
+	/// connections.
+	pub fn host_key_fingerprint(&self) -> Option<String> {
+		host_key::host_key_fingerprint(&self.sess)
+	}
+
+	/// The authentication method that succeeded while connecting.
+	pub fn auth_method(&self) -> AuthMethod {
+		self.auth_method
+	}
+
+	/// Change how long to wait for the prompt after a command was sent, e.g. for commands that
+	/// take a long time to finish.
+	pub fn set_command_timeout(&mut self, timeout: Duration) {
+		self.command_timeout = timeout;
+	}
+}
+
+impl Connection for SSHConnection {
+	type ConnectionHandler = SSHConnection;
+
+	/// Connect to the device using SSH. All authentication methods the builder has credentials
+	/// for are tried in order, until one of them succeeds.
+	fn connect_with(builder: &ConnectionBuilder) -> Result<SSHConnection> {
+		let (sess, auth_method, _) = Self::open_session(builder)?;
+		let channel = Self::open_shell(&sess, builder)?;
+
+		// From now on the timeout only applies to single reads, the command timeout is checked
+		// in between them.
+		sess.set_timeout(builder.read_timeout.as_millis() as u32);
+
+		Ok(SSHConnection {
+			sess,
+			channel,
+			auth_method,
+			command_timeout: builder.command_timeout,
+			line_ending: builder.line_ending.clone(),
+		})
+	}
+
+	fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		self.channel.write_all(data)?;
+		Ok(())
+	}
+
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+		let mut buf = [0u8; 1024];
+
+		let size = match self.channel.read(&mut buf) {
+			Ok(s) => s,
+			Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {
+				debug!("Timed out... Assuming no data");
+				return Ok(None);
+			}
+			Err(e) => return Err(e.into()),
+		};
+		if size == 0 {
+			debug!("Channel closed by the server");
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Channel closed by the server").into());
+		}
+
+		Ok(Some(buf[..size].to_vec()))
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+use log::debug;
+use telnet::{Event, Telnet};
+use regex::Regex;
+use crate::devices::generic::connection::{Connection, ConnectionBuilder, DEFAULT_TELNET_PORT};
+use crate::error::{Error, Result};
+
+/// A TelnetConnection is a Connection type, that uses Telnet to connect to the device.
+pub struct TelnetConnection {
+	conn: Telnet,
+	read_timeout: Duration,
+	command_timeout: Duration,
+	line_ending: String,
+}
+
+impl TelnetConnection {
+	/// Change how long to wait for the prompt after a command was sent, e.g. for commands that
+	/// take a long time to finish.
+	pub fn set_command_timeout(&mut self, timeout: Duration) {
+		self.command_timeout = timeout;
+	}
+}
+
+impl Connection for TelnetConnection {
+	type ConnectionHandler = TelnetConnection;
+
+	/// Connect to the device using telnet with an optional username and password which are sent
+	/// to the device right after the connection is made. The whole login has to finish within the
+	/// connect timeout.
+	fn connect_with(builder: &ConnectionBuilder) -> Result<TelnetConnection> {
+		let (stream, _, _) = builder.connect_tcp(DEFAULT_TELNET_PORT)?;
+		let mut conn = TelnetConnection {
+			conn: Telnet::from_stream(Box::new(stream), 1024),
+			read_timeout: builder.read_timeout,
+			command_timeout: builder.connect_timeout,
+			line_ending: builder.line_ending.clone(),
+		};
+
+		// Authenticate
+		if let Some(username) = &builder.username {
+			conn.execute_raw(username, &Regex::new("[Pp]assword:?")?)?;
+
+			if let Some(password) = &builder.password {
+				let line = format!("{}{}", password, conn.line_ending);
+				conn.write_raw(line.as_bytes())?;
+				let output = conn.read_until_prompt(&Regex::new("[>#%$]|[Uu]sername:?|[Ll]ogin:?")?)?;
+
+				if Regex::new(r"([Uu]sername|[Ll]ogin):?\s*$")?.is_match(&output) {
+					return Err(Error::authentication("The device asked for the login again, the credentials were rejected"));
+				}
+			}
+		}
+
+		conn.command_timeout = builder.command_timeout;
+		Ok(conn)
+	}
+
+	fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+		self.conn.write(data)?;
+		Ok(())
+	}
+
+	/// Read the next chunk of data, skipping any telnet negotiation in between.
+	fn read_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+		loop {
+			match self.conn.read_timeout(self.read_timeout)? {
+				Event::TimedOut => return Ok(None),
+				Event::Data(data) => return Ok(Some(data.into_vec())),
+				Event::Error(e) => return Err(e.into()),
+				event => debug!("Discarded event: {:?}", event),
+			}
+		}
+	}
+
+	fn command_timeout(&self) -> Duration {
+		self.command_timeout
+	}
+
+	fn line_ending(&self) -> &str {
+		&self.line_ending
+	}
+}
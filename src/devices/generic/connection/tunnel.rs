@@ -0,0 +1,149 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use log::debug;
+use ssh2::{Channel, Session};
+use crate::error::{Error, Result};
+
+/// How long the forwarding thread sleeps when neither side has data.
+const IDLE_SLEEP: Duration = Duration::from_millis(2);
+
+/// Create a connected pair of TCP streams on the loopback interface. The first one is handed to
+/// the connection, the second one is fed by the tunnel.
+fn loopback_pair() -> Result<(TcpStream, TcpStream)> {
+	let listener = TcpListener::bind("127.0.0.1:0")?;
+	let local = TcpStream::connect(listener.local_addr()?)?;
+	let (remote, peer) = listener.accept()?;
+
+	// Make sure nobody else raced us to the listener.
+	if peer != local.local_addr()? {
+		return Err(io::Error::new(io::ErrorKind::ConnectionRefused, "Unexpected connection to the tunnel").into());
+	}
+	Ok((local, remote))
+}
+
+/// Forward a direct-tcpip channel of a jump host to a local stream. The session of the jump host
+/// is kept alive by the forwarding thread until either side closes the connection.
+pub(crate) fn forward_channel(session: Session, mut channel: Channel) -> Result<TcpStream> {
+	let (local, mut remote) = loopback_pair()?;
+	remote.set_nonblocking(true)?;
+	session.set_blocking(false);
+
+	thread::spawn(move || {
+		let _session = session;
+		let mut buf = [0u8; 16384];
+		let mut to_channel: Vec<u8> = Vec::new();
+		let mut to_socket: Vec<u8> = Vec::new();
+
+		loop {
+			let mut idle = true;
+
+			if to_channel.is_empty() {
+				match remote.read(&mut buf) {
+					Ok(0) => break,
+					Ok(size) => to_channel.extend_from_slice(&buf[..size]),
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => {},
+					Err(_) => break,
+				}
+			}
+			if !to_channel.is_empty() {
+				match channel.write(&to_channel) {
+					Ok(size) => {
+						to_channel.drain(..size);
+						idle = false;
+					},
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => {},
+					Err(_) => break,
+				}
+			}
+
+			if to_socket.is_empty() {
+				match channel.read(&mut buf) {
+					Ok(0) if channel.eof() => break,
+					Ok(0) => {},
+					Ok(size) => to_socket.extend_from_slice(&buf[..size]),
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => {},
+					Err(_) => break,
+				}
+			}
+			if !to_socket.is_empty() {
+				match remote.write(&to_socket) {
+					Ok(size) => {
+						to_socket.drain(..size);
+						idle = false;
+					},
+					Err(e) if e.kind() == io::ErrorKind::WouldBlock => {},
+					Err(_) => break,
+				}
+			}
+
+			if idle {
+				thread::sleep(IDLE_SLEEP);
+			}
+		}
+
+		debug!("Jump host tunnel closed");
+		let _ = channel.close();
+	});
+
+	Ok(local)
+}
+
+/// Run a ProxyCommand like `ssh -W %h:%p bastion` and connect its stdin and stdout to a local
+/// stream. `%h` is replaced with the host, `%p` with the port and `%%` with a literal `%`.
+pub(crate) fn proxy_command(command: &str, host: &str, port: u16) -> Result<TcpStream> {
+	let command = command
+		.replace("%%", "\0")
+		.replace("%h", host)
+		.replace("%p", &port.to_string())
+		.replace('\0', "%");
+	debug!("Running proxy command: {}", command);
+
+	let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+	let mut child = Command::new(shell)
+		.arg(flag)
+		.arg(&command)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()?;
+	let (Some(mut stdin), Some(mut stdout)) = (child.stdin.take(), child.stdout.take()) else {
+		return Err(Error::Io(io::Error::other("The proxy command has no stdin or stdout")));
+	};
+
+	let (local, remote) = loopback_pair()?;
+	let mut remote_read = remote.try_clone()?;
+	let mut remote_write = remote;
+
+	thread::spawn(move || {
+		pump(&mut remote_read, &mut stdin);
+		drop(stdin);
+		let _ = child.wait();
+		debug!("Proxy command finished");
+	});
+	thread::spawn(move || {
+		pump(&mut stdout, &mut remote_write);
+		let _ = remote_write.shutdown(std::net::Shutdown::Both);
+	});
+
+	Ok(local)
+}
+
+/// Copy everything from `from` to `to` chunk by chunk. `io::copy` is not used here because its
+/// splice fast path on Linux can hold back small interactive writes between sockets and pipes.
+fn pump(from: &mut impl Read, to: &mut impl Write) {
+	let mut buf = [0u8; 16384];
+	loop {
+		match from.read(&mut buf) {
+			Ok(0) => break,
+			Ok(size) => {
+				if to.write_all(&buf[..size]).and_then(|_| to.flush()).is_err() {
+					break;
+				}
+			},
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+			Err(_) => break,
+		}
+	}
+}
@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use log::warn;
+use crate::devices::generic::device_types::interfaces::Interface;
+use crate::error::Result;
+
+/// The async counterpart of [`Configurable`].
+///
+/// [`Configurable`]: crate::devices::generic::device_types::config::Configurable
+#[async_trait]
+pub trait AsyncConfigurable: Send {
+	type SessionType: AsyncConfigurable;
+
+	/// Enter the configuration mode
+	async fn enter_config(&mut self) -> Result<AsyncConfigurationMode<'_, Self::SessionType>>;
+	/// Execute a raw command on the device, the state here can't be checked or enforced anymore.
+	async fn execute_raw(&mut self, command: &str) -> Result<()>;
+	/// Send a command to the device and return its output, without the echoed command and the
+	/// trailing prompt.
+	async fn send_command(&mut self, command: &str) -> Result<String>;
+	/// Exit the current state.
+	async fn exit(&mut self) -> Result<()>;
+	/// Enter a nested configuration state with a command like `interface gi0/1` or
+	/// `edit interfaces`. Devices that track their mode check that the command led there.
+	async fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+		self.execute_raw(command).await
+	}
+	/// Leave the configuration mode entirely, from whichever configuration state the session is
+	/// in. Does the same as [`AsyncConfigurable::exit`] unless the device knows better.
+	async fn exit_config(&mut self) -> Result<()> {
+		self.exit().await
+	}
+	/// Throw away the changes that weren't applied yet and leave the configuration mode, like
+	/// [`Configurable::abort_config`].
+	///
+	/// [`Configurable::abort_config`]: crate::devices::generic::device_types::config::Configurable::abort_config
+	async fn abort_config(&mut self) -> Result<()> {
+		self.exit_config().await
+	}
+}
+
+/// The async counterpart of [`InterfaceConfigurable`].
+///
+/// [`InterfaceConfigurable`]: crate::devices::generic::device_types::config::InterfaceConfigurable
+#[async_trait]
+pub trait AsyncInterfaceConfigurable {
+	/// Configures an interface to be available.
+	async fn interface_up(&mut self, interface: &Interface) -> Result<()>;
+	/// Configures an interface to not be available.
+	async fn interface_down(&mut self, interface: &Interface) -> Result<()>;
+}
+
+/// The async counterpart of [`ConfigurationMode`].
+///
+/// Leaving the configuration mode needs to send a command, which can't be awaited when the object
+/// is dropped. Call [`finish`] or [`abort`] when done, otherwise the session stays in configuration
+/// mode.
+///
+/// ```ignore
+/// let mut device = AsyncCiscoTelnet::connect_async("127.0.0.1:23", "admin", "admin").await?;
+/// let mut config = device.enter_config().await?;
+/// let interface = config.get_interface("FastEthernet", &[0, 1]);
+/// config.interface_up(&interface).await?;
+/// config.finish().await?;
+/// ```
+///
+/// [`ConfigurationMode`]: crate::devices::generic::device_types::config::ConfigurationMode
+/// [`finish`]: AsyncConfigurationMode::finish
+/// [`abort`]: AsyncConfigurationMode::abort
+pub struct AsyncConfigurationMode<'a, T: AsyncConfigurable> {
+	pub(crate) session: &'a mut T,
+	exited: bool,
+}
+
+impl<'a, T: AsyncConfigurable> AsyncConfigurationMode<'a, T> {
+	/// Enter the configuration mode and steal the session while in configuration mode.
+	pub fn enter(session: &mut T) -> AsyncConfigurationMode<'_, T> {
+		AsyncConfigurationMode {
+			session,
+			exited: false,
+		}
+	}
+
+	/// Will build an interface string from a name and indices \[name]((group)/subgroup/index)
+	pub fn get_interface(&self, name: &str, indices: &[u32]) -> Interface {
+		Interface::from_indices(name, indices)
+	}
+
+	/// Execute any raw command on the device from configuration mode
+	pub async fn execute_raw(&mut self, command: &str) -> Result<()> {
+		self.session.execute_raw(command).await
+	}
+
+	/// Send any command on the device from configuration mode and return its output
+	pub async fn send_command(&mut self, command: &str) -> Result<String> {
+		self.session.send_command(command).await
+	}
+
+	/// Enter a nested configuration state with any command, like `router bgp 65000`. Call
+	/// [`AsyncSubMode::exit`] on the returned guard to leave it again.
+	pub async fn sub_mode(&mut self, command: &str) -> Result<AsyncSubMode<'_, T>> {
+		AsyncSubMode::enter(self.session, command).await
+	}
+
+	/// Leave the configuration mode and free the session again, like
+	/// [`ConfigurationMode::finish`].
+	///
+	/// [`ConfigurationMode::finish`]: crate::devices::generic::device_types::config::ConfigurationMode::finish
+	pub async fn finish(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.exit_config().await
+	}
+
+	/// Throw away the changes that weren't committed yet and leave the configuration mode, like
+	/// [`ConfigurationMode::abort`].
+	///
+	/// [`ConfigurationMode::abort`]: crate::devices::generic::device_types::config::ConfigurationMode::abort
+	pub async fn abort(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.abort_config().await
+	}
+}
+
+impl<T: AsyncConfigurable> Drop for AsyncConfigurationMode<'_, T> {
+	fn drop(&mut self) {
+		if !self.exited {
+			warn!("Configuration mode was dropped without calling finish() or abort(), the session is still in configuration mode");
+		}
+	}
+}
+
+/// The async counterpart of [`SubMode`]. Like [`AsyncConfigurationMode`], it can't leave the
+/// sub-mode when dropped, so call [`exit`] when done.
+///
+/// [`SubMode`]: crate::devices::generic::device_types::config::SubMode
+/// [`exit`]: AsyncSubMode::exit
+pub struct AsyncSubMode<'a, T: AsyncConfigurable> {
+	pub(crate) session: &'a mut T,
+	exited: bool,
+}
+
+impl<'a, T: AsyncConfigurable> AsyncSubMode<'a, T> {
+	/// Enter a nested configuration state with the given command.
+	pub async fn enter(session: &'a mut T, command: &str) -> Result<AsyncSubMode<'a, T>> {
+		session.enter_sub_mode(command).await?;
+		Ok(AsyncSubMode {
+			session,
+			exited: false,
+		})
+	}
+
+	/// Execute any raw command on the device from this sub-mode
+	pub async fn execute_raw(&mut self, command: &str) -> Result<()> {
+		self.session.execute_raw(command).await
+	}
+
+	/// Send any command on the device from this sub-mode and return its output
+	pub async fn send_command(&mut self, command: &str) -> Result<String> {
+		self.session.send_command(command).await
+	}
+
+	/// Enter a sub-mode nested in this one, like `address-family ipv4` below `router bgp`.
+	pub async fn sub_mode(&mut self, command: &str) -> Result<AsyncSubMode<'_, T>> {
+		AsyncSubMode::enter(self.session, command).await
+	}
+
+	/// Go back to the state this sub-mode was entered from.
+	pub async fn exit(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.exit().await
+	}
+}
+
+impl<T: AsyncConfigurable> Drop for AsyncSubMode<'_, T> {
+	fn drop(&mut self) {
+		if !self.exited {
+			warn!("Sub-mode was dropped without calling exit(), the session is still in it");
+		}
+	}
+}
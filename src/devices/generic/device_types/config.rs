@@ -1,5 +1,6 @@
-use std::io;
+use log::warn;
 use crate::devices::generic::device_types::interfaces::Interface;
+use crate::error::Result;
 
 /// This trait describes a configurable device. It can enter a sort of configuration mode and
 /// execute commands in this mode, as well as exit the config mode and save.
@@ -7,70 +8,199 @@ pub trait Configurable {
 	type SessionType: Configurable;
 
 	/// Enter the configuration mode
-	fn enter_config(&mut self) -> io::Result<ConfigurationMode<Self::SessionType>>;
+	fn enter_config(&mut self) -> Result<ConfigurationMode<'_, Self::SessionType>>;
 	/// Execute a raw command on the device, the state here can't be checked or enforced anymore.
-	fn execute_raw(&mut self, command: &str) -> io::Result<()>;
+	fn execute_raw(&mut self, command: &str) -> Result<()>;
+	/// Send a command to the device and return its output, without the echoed command and the
+	/// trailing prompt.
+	fn send_command(&mut self, command: &str) -> Result<String>;
 	/// Exit the current state.
-	fn exit(&mut self) -> io::Result<()>;
+	fn exit(&mut self) -> Result<()>;
+	/// Enter a nested configuration state with a command like `interface gi0/1` or
+	/// `edit interfaces`. Devices that track their mode check that the command led there.
+	fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+		self.execute_raw(command)
+	}
+	/// Leave the configuration mode entirely, from whichever configuration state the session is
+	/// in. Does the same as [`Configurable::exit`] unless the device knows better.
+	fn exit_config(&mut self) -> Result<()> {
+		self.exit()
+	}
+	/// Throw away the changes that weren't applied yet and leave the configuration mode. Devices
+	/// which apply every command right away, like IOS, just leave it with
+	/// [`Configurable::exit_config`].
+	fn abort_config(&mut self) -> Result<()> {
+		self.exit_config()
+	}
 }
 
 /// This trait describes a device that has interfaces it can bring up or down.
 pub trait InterfaceConfigurable {
 	/// Configures an interface to be available.
-	fn interface_up(&mut self, interface: &Interface) -> io::Result<()>;
+	fn interface_up(&mut self, interface: &Interface) -> Result<()>;
 	/// Configures an interface to not be available.
-	fn interface_down(&mut self, interface: &Interface) -> io::Result<()>;
+	fn interface_down(&mut self, interface: &Interface) -> Result<()>;
 }
 
 /// A ConfigurationMode object exists for the purpose of encapsulating the configuration mode from
 /// the "normal mode". When you configure a device, usually you enter a special mode with a custom
-/// command set. This will lock the session into the configure mode until the object is dropped.
+/// command set. This will lock the session into the configure mode until [`finish`] or [`abort`]
+/// is called, which return an error if the session couldn't leave it.
 ///
 /// ```ignore
 /// let device = CiscoTelnet::new("127.0.0.1:23", "admin", "admin");
-/// {
-/// 	// locks device
-/// 	let config = device.enter_config()?;
-/// 	let interface = config.get_interface("FastEthernet", &[0, 1]);
-/// 	config.interface_up(&interface);
-/// }
-/// // able to use device here again after Drop
+/// // locks device
+/// let config = device.enter_config()?;
+/// let interface = config.get_interface("FastEthernet", &[0, 1]);
+/// config.interface_up(&interface)?;
+/// config.finish()?;
+/// // able to use device here again
 /// ```
 ///
+/// Dropping the object without either also leaves the configuration mode, but an error can only be
+/// logged there.
+///
+/// [`finish`]: ConfigurationMode::finish
+/// [`abort`]: ConfigurationMode::abort
 pub struct ConfigurationMode<'a, T: Configurable> {
 	pub(crate) session: &'a mut T,
+	exited: bool,
 }
 
 impl<'a, T: Configurable> ConfigurationMode<'a, T> {
 	/// Will build an interface string from a name and indices \[name]((group)/subgroup/index)
 	pub fn get_interface(&self, name: &str, indices: &[u32]) -> Interface {
-		let indices_str: String = indices.iter()
-			.map(|index| index.to_string())
-			.collect::<Vec<String>>()
-			.join("/");
-		Interface::new(format!("{}{}", name, indices_str))
+		Interface::from_indices(name, indices)
 	}
 }
 
 impl<'a, T: Configurable> ConfigurationMode<'a, T> {
 	/// Enter the configuration mode and steal the session while in configuration mode.
-	pub fn enter(session: &mut T) -> ConfigurationMode<T> {
+	pub fn enter(session: &mut T) -> ConfigurationMode<'_, T> {
 		ConfigurationMode {
 			session,
+			exited: false,
 		}
 	}
 
 	/// Execute any raw command on the device from configuration mode
-	pub fn execute_raw(&mut self, command: &str) -> io::Result<()> {
+	pub fn execute_raw(&mut self, command: &str) -> Result<()> {
 		self.session.execute_raw(command)
 	}
+
+	/// Send any command on the device from configuration mode and return its output
+	pub fn send_command(&mut self, command: &str) -> Result<String> {
+		self.session.send_command(command)
+	}
+
+	/// Enter a nested configuration state with any command, like `router bgp 65000`. The returned
+	/// guard leaves it again when dropped.
+	pub fn sub_mode(&mut self, command: &str) -> Result<SubMode<'_, T>> {
+		SubMode::enter(self.session, command)
+	}
+
+	/// Leave the configuration mode and free the session again. Changes which still need to be
+	/// committed, like on Junos, are kept as they are.
+	pub fn finish(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.exit_config()
+	}
+
+	/// Throw away the changes that weren't committed yet and leave the configuration mode, e.g.
+	/// with `rollback 0` on Junos.
+	pub fn abort(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.abort_config()
+	}
 }
 
 impl<T: Configurable> Drop for ConfigurationMode<'_, T> {
 	/// Drops the ConfigurationMode object at the end of the scope
 	/// To free the locked session, making it usable again.
-	/// Also exits the current state in the session.
+	/// Also leaves the configuration mode in the session, unless [`ConfigurationMode::finish`]
+	/// or [`ConfigurationMode::abort`] already did.
 	fn drop(&mut self) {
-		let _ = self.session.exit();
+		if self.exited {
+			return;
+		}
+		if let Err(e) = self.session.exit_config() {
+			warn!("Failed to leave the configuration mode: {}", e);
+		}
 	}
-}
\ No newline at end of file
+}
+
+/// A SubMode guard encapsulates a nested configuration state, like the configuration of an
+/// interface on IOS or an `edit` hierarchy on Junos. It borrows the configuration mode it was
+/// entered from and goes back there when it is dropped, so nested configuration can't leave the
+/// session somewhere unexpected.
+///
+/// ```ignore
+/// let mut config = device.enter_config()?;
+/// {
+///     let mut ospf = config.router_ospf(1)?;
+///     ospf.execute_raw("network 10.0.0.0 0.0.0.255 area 0")?;
+///     let mut af = ospf.sub_mode("address-family ipv4")?;
+///     // nested sub-modes work the same way
+/// }
+/// // back in global configuration mode
+/// ```
+pub struct SubMode<'a, T: Configurable> {
+	pub(crate) session: &'a mut T,
+	exited: bool,
+}
+
+/// The guard for the configuration of an interface.
+pub type InterfaceMode<'a, T> = SubMode<'a, T>;
+/// The guard for the configuration of a routing protocol, like `router ospf 1` on IOS.
+pub type RouterMode<'a, T> = SubMode<'a, T>;
+/// The guard for the configuration of terminal lines, like `line vty 0 4` on IOS.
+pub type LineMode<'a, T> = SubMode<'a, T>;
+/// The guard for the configuration of a VLAN, like `vlan 10` on IOS.
+pub type VlanMode<'a, T> = SubMode<'a, T>;
+/// The guard for an `edit` hierarchy on Junos.
+pub type EditMode<'a, T> = SubMode<'a, T>;
+
+impl<'a, T: Configurable> SubMode<'a, T> {
+	/// Enter a nested configuration state with the given command.
+	pub fn enter(session: &'a mut T, command: &str) -> Result<SubMode<'a, T>> {
+		session.enter_sub_mode(command)?;
+		Ok(SubMode {
+			session,
+			exited: false,
+		})
+	}
+
+	/// Execute any raw command on the device from this sub-mode
+	pub fn execute_raw(&mut self, command: &str) -> Result<()> {
+		self.session.execute_raw(command)
+	}
+
+	/// Send any command on the device from this sub-mode and return its output
+	pub fn send_command(&mut self, command: &str) -> Result<String> {
+		self.session.send_command(command)
+	}
+
+	/// Enter a sub-mode nested in this one, like `address-family ipv4` below `router bgp`.
+	pub fn sub_mode(&mut self, command: &str) -> Result<SubMode<'_, T>> {
+		SubMode::enter(self.session, command)
+	}
+
+	/// Go back to the state this sub-mode was entered from. Unlike dropping the guard, this
+	/// returns the error if that fails.
+	pub fn exit(mut self) -> Result<()> {
+		self.exited = true;
+		self.session.exit()
+	}
+}
+
+impl<T: Configurable> Drop for SubMode<'_, T> {
+	/// Goes back to the state this sub-mode was entered from.
+	fn drop(&mut self) {
+		if self.exited {
+			return;
+		}
+		if let Err(e) = self.session.exit() {
+			warn!("Failed to leave the sub-mode: {}", e);
+		}
+	}
+}
@@ -11,6 +11,15 @@ impl Interface {
 		}
 	}
 
+	/// Build an interface from a name and indices, e.g. `gi` and `[0, 1]` become `gi0/1`.
+	pub(crate) fn from_indices(name: &str, indices: &[u32]) -> Interface {
+		let indices_str: String = indices.iter()
+			.map(|index| index.to_string())
+			.collect::<Vec<String>>()
+			.join("/");
+		Interface::new(format!("{}{}", name, indices_str))
+	}
+
 	/// Get the name of the interface.
 	pub fn name(&self) -> &str {
 		&self.name
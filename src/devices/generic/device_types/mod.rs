@@ -1,4 +1,6 @@
 //! The device type module contains configurable features that devices can inherit.
 //! They are plug-and-play and can be used when seen fit, and implemented on a per-device level.
 pub mod interfaces;
-pub mod config;
\ No newline at end of file
+pub mod config;
+#[cfg(feature = "async")]
+pub mod async_config;
\ No newline at end of file
@@ -0,0 +1,61 @@
+use regex::Regex;
+use crate::error::{Error, Result};
+
+/// A set of patterns that identify output of a command the device rejected, like
+/// `% Invalid input detected` on cisco or `syntax error` on juniper.
+///
+/// Every device comes with a predefined set for its vendor, which can be extended with custom
+/// patterns:
+///
+/// ```ignore
+/// let mut cisco = CiscoTelnet::connect("192.168.1.101:23", "admin", "admin")?;
+/// cisco.error_patterns_mut().add(r"^% Not allowed")?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ErrorPatterns {
+	patterns: Vec<Regex>,
+}
+
+impl ErrorPatterns {
+	/// Create a new set of error patterns. Each pattern is matched against every line of the
+	/// output on its own.
+	pub fn new(patterns: &[&str]) -> Result<ErrorPatterns> {
+		let mut error_patterns = ErrorPatterns::default();
+		for pattern in patterns {
+			error_patterns.add(pattern)?;
+		}
+		Ok(error_patterns)
+	}
+
+	/// Add a pattern to the set.
+	pub fn add(&mut self, pattern: &str) -> Result<()> {
+		self.patterns.push(Regex::new(&format!("(?m){}", pattern))?);
+		Ok(())
+	}
+
+	/// Remove all patterns, so no output is considered an error anymore.
+	pub fn clear(&mut self) {
+		self.patterns.clear();
+	}
+
+	/// Get all patterns in this set.
+	pub fn patterns(&self) -> &[Regex] {
+		&self.patterns
+	}
+
+	/// Returns true if any of the patterns matches the output.
+	pub fn is_error(&self, output: &str) -> bool {
+		self.patterns.iter().any(|pattern| pattern.is_match(output))
+	}
+
+	/// Turns the output of a command into a [`Error::CommandFailed`] if it matches any pattern.
+	pub fn check(&self, command: &str, output: String) -> Result<String> {
+		if self.is_error(&output) {
+			return Err(Error::CommandFailed {
+				command: command.to_string(),
+				output,
+			});
+		}
+		Ok(output)
+	}
+}
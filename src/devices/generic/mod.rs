@@ -1,4 +1,5 @@
 //! This module defines all the predefined generic traits and structs that can be used by device
 //! implementations to implement their features, availability, I/O and usage.
 pub mod connection;
-pub mod device_types;
\ No newline at end of file
+pub mod device_types;
+pub mod error_patterns;
\ No newline at end of file
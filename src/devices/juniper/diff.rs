@@ -0,0 +1,237 @@
+/// The difference between the candidate and the active configuration of a Junos device, as shown
+/// by `show | compare`.
+///
+/// Besides the raw output, every added and removed statement is parsed with the hierarchy level
+/// it belongs to, so a change can be checked against the expected diff before it is committed.
+///
+/// ```
+/// use rustmiko::devices::juniper::ConfigDiff;
+///
+/// let diff = ConfigDiff::parse("\
+/// [edit system]
+/// -  host-name srx;
+/// +  host-name core1;
+/// [edit interfaces]
+/// +   ge-0/0/3 {
+/// +       description uplink;
+/// +   }");
+///
+/// assert_eq!(diff.removed()[0].statement(), "system host-name srx");
+/// assert_eq!(diff.added()[0].statement(), "system host-name core1");
+/// assert_eq!(diff.added()[1].statement(), "interfaces ge-0/0/3 description uplink");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiff {
+    raw: String,
+    added: Vec<DiffLine>,
+    removed: Vec<DiffLine>,
+}
+
+/// A statement that was added or removed, at the hierarchy level it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    hierarchy: String,
+    line: String,
+}
+
+impl ConfigDiff {
+    /// Parses the output of `show | compare`. Lines that aren't part of the diff are ignored.
+    pub fn parse(raw: &str) -> ConfigDiff {
+        let mut diff = ConfigDiff {
+            raw: raw.to_string(),
+            added: Vec::new(),
+            removed: Vec::new(),
+        };
+        let mut section = String::new();
+        let mut levels: Vec<String> = Vec::new();
+
+        for line in raw.lines() {
+            if let Some(path) = Self::section(line) {
+                section = path.to_string();
+                levels.clear();
+                continue;
+            }
+
+            let Some(sign) = line.chars().next().filter(|c| matches!(c, '+' | '-' | ' ')) else {
+                continue;
+            };
+            let text = line[1..].trim();
+            if let Some(level) = text.strip_suffix('{') {
+                levels.push(level.trim_end().to_string());
+                continue;
+            }
+            if text == "}" {
+                levels.pop();
+                continue;
+            }
+            if text.is_empty() || sign == ' ' {
+                continue;
+            }
+
+            let hierarchy = std::iter::once(section.as_str())
+                .chain(levels.iter().map(String::as_str))
+                .filter(|level| !level.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" ");
+            let line = DiffLine {
+                hierarchy,
+                line: text.trim_end_matches(';').to_string(),
+            };
+            match sign {
+                '+' => diff.added.push(line),
+                _ => diff.removed.push(line),
+            }
+        }
+
+        diff
+    }
+
+    /// The path in a section header like `[edit interfaces ge-0/0/0]`.
+    fn section(line: &str) -> Option<&str> {
+        let path = line.trim().strip_prefix("[edit")?.strip_suffix(']')?;
+        match path.is_empty() || path.starts_with(' ') {
+            true => Some(path.trim()),
+            false => None,
+        }
+    }
+
+    /// The output of `show | compare` as the device sent it.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The statements that are in the candidate, but not in the active configuration.
+    pub fn added(&self) -> &[DiffLine] {
+        &self.added
+    }
+
+    /// The statements that are in the active, but not in the candidate configuration.
+    pub fn removed(&self) -> &[DiffLine] {
+        &self.removed
+    }
+
+    /// Whether the candidate configuration is the same as the active one.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl DiffLine {
+    /// The hierarchy level of the statement, e.g. `interfaces ge-0/0/0 unit 0`. It's empty at the
+    /// top of the configuration.
+    pub fn hierarchy(&self) -> &str {
+        &self.hierarchy
+    }
+
+    /// The statement as it was shown at its hierarchy level, without the `;`, e.g. `mtu 9000`.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// The whole statement from the top of the configuration, like it would be written after `set`
+    /// or `delete`, e.g. `interfaces ge-0/0/0 unit 0 mtu 9000`.
+    pub fn statement(&self) -> String {
+        match self.hierarchy.is_empty() {
+            true => self.line.clone(),
+            false => format!("{} {}", self.hierarchy, self.line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConfigDiff;
+
+    fn statements(lines: &[super::DiffLine]) -> Vec<String> {
+        lines.iter().map(|line| line.statement()).collect()
+    }
+
+    #[test]
+    fn parses_multiple_sections() {
+        let diff = ConfigDiff::parse("\
+[edit system]
+-  host-name srx;
++  host-name core1;
+[edit interfaces ge-0/0/0 unit 0 family inet]
++       address 10.0.0.1/24;
+-       address 192.168.1.1/24;
+[edit interfaces]
+-   ge-0/0/2 {
+-       description old;
+-   }
++   ge-0/0/3 {
++       description uplink;
++       unit 0 {
++           family inet {
++               address 10.1.0.1/30;
++           }
++       }
++   }
+[edit]
++  snmp {
++      community public;
++  }");
+
+        assert_eq!(statements(diff.added()), [
+            "system host-name core1",
+            "interfaces ge-0/0/0 unit 0 family inet address 10.0.0.1/24",
+            "interfaces ge-0/0/3 description uplink",
+            "interfaces ge-0/0/3 unit 0 family inet address 10.1.0.1/30",
+            "snmp community public",
+        ]);
+        assert_eq!(statements(diff.removed()), [
+            "system host-name srx",
+            "interfaces ge-0/0/0 unit 0 family inet address 192.168.1.1/24",
+            "interfaces ge-0/0/2 description old",
+        ]);
+        assert_eq!(diff.added()[1].hierarchy(), "interfaces ge-0/0/0 unit 0 family inet");
+        assert_eq!(diff.added()[1].line(), "address 10.0.0.1/24");
+        assert_eq!(diff.added()[4].hierarchy(), "snmp");
+    }
+
+    #[test]
+    fn context_lines_only_set_the_hierarchy() {
+        let diff = ConfigDiff::parse("\
+[edit security policies from-zone trust to-zone untrust]
+     policy allow-all { ... }
++    policy allow-web {
++        match {
++            application junos-http;
++        }
++    }
+[edit system]
+     services {
++        netconf {
++            ssh;
++        }
+-        telnet;
+     }
+     syslog {
+         file messages { ... }
+     }");
+
+        assert_eq!(statements(diff.added()), [
+            "security policies from-zone trust to-zone untrust policy allow-web match application junos-http",
+            "system services netconf ssh",
+        ]);
+        assert_eq!(statements(diff.removed()), ["system services telnet"]);
+    }
+
+    #[test]
+    fn empty_output_has_no_changes() {
+        let diff = ConfigDiff::parse("");
+        assert!(diff.is_empty());
+        assert_eq!(diff.raw(), "");
+
+        let diff = ConfigDiff::parse("\n");
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_raw_output() {
+        let raw = "[edit system]\n-  host-name srx;\n+  host-name core1;";
+        let diff = ConfigDiff::parse(raw);
+        assert_eq!(diff.raw(), raw);
+        assert!(!diff.is_empty());
+    }
+}
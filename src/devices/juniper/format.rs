@@ -0,0 +1,27 @@
+/// The format in which [`get_config`] returns the configuration.
+///
+/// [`get_config`]: crate::devices::juniper::JuniperDevice::get_config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConfigFormat {
+    /// The curly brace format, like `show configuration`.
+    #[default]
+    Text,
+    /// One `set` command per statement, like `show configuration | display set`.
+    Set,
+    /// The Junos XML, like `show configuration | display xml`.
+    Xml,
+    /// The Junos JSON, like `show configuration | display json`.
+    Json,
+}
+
+impl ConfigFormat {
+    /// The pipe that makes `show configuration` use this format, e.g. ` | display set`.
+    pub fn pipe(&self) -> &'static str {
+        match self {
+            ConfigFormat::Text => "",
+            ConfigFormat::Set => " | display set",
+            ConfigFormat::Xml => " | display xml",
+            ConfigFormat::Json => " | display json",
+        }
+    }
+}
@@ -1,10 +1,42 @@
-use std::error::Error;
-use std::io;
 use std::net::ToSocketAddrs;
+use std::path::Path;
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+use log::warn;
 use regex::Regex;
-use crate::devices::generic::connection::Connection;
-use crate::devices::generic::device_types::config::{Configurable, ConfigurationMode, InterfaceConfigurable};
+#[cfg(feature = "async")]
+use crate::devices::generic::connection::AsyncConnection;
+use crate::devices::generic::connection::{last_line, strip_command_and_prompt, Connection, ConnectionBuilder};
+#[cfg(feature = "async")]
+use crate::devices::generic::device_types::async_config::{AsyncConfigurable, AsyncConfigurationMode, AsyncInterfaceConfigurable, AsyncSubMode};
+use crate::devices::generic::device_types::config::{Configurable, ConfigurationMode, EditMode, InterfaceConfigurable, InterfaceMode, SubMode};
 use crate::devices::generic::device_types::interfaces::Interface;
+use crate::devices::generic::error_patterns::ErrorPatterns;
+use crate::devices::juniper::{ConfigDiff, ConfigFormat, LoadAction};
+use crate::error::{Error, Result};
+
+/// Output that marks a command as rejected by Junos.
+const ERROR_PATTERNS: &[&str] = &[
+    r"^\s*syntax error",
+    r"^\s*unknown command",
+    r"^error: configuration check-out failed",
+    r"^error:",
+];
+
+/// The question Junos asks when leaving the configuration mode with uncommitted changes.
+const CONFIRM_EXIT: &str = r"^Exit with uncommitted changes\? \[yes,no\] \(yes\)$";
+
+/// The line Junos shows before it reads the input of `load ... terminal`.
+const LOAD_INPUT: &str = r"(?m)^\[Type \^D at a new line to end input\]$";
+/// Ctrl-D, which ends the input of `load ... terminal`.
+const END_OF_INPUT: char = '\x04';
+
+/// Output of a successful commit.
+const COMMIT_COMPLETE: &str = "commit complete";
+/// Output of a successful `commit check`.
+const CHECK_SUCCEEDS: &str = "configuration check succeeds";
+/// Output of a successful `rollback`.
+const LOAD_COMPLETE: &str = "load complete";
 
 /// A juniper (EX) device API implementation.
 ///
@@ -13,26 +45,415 @@ use crate::devices::generic::device_types::interfaces::Interface;
 /// [`JuniperSSH`] or
 /// [`JuniperTelnet`]
 ///
+///
+/// Other connections like a [`MockConnection`] or a [`ReplayConnection`] can be used with
+/// `from_connection`, e.g. to test code that drives a device:
+///
+/// ```
+/// use rustmiko::devices::generic::connection::MockConnection;
+/// use rustmiko::devices::generic::device_types::config::Configurable;
+/// use rustmiko::devices::juniper::JuniperDevice;
+///
+/// let mock = MockConnection::new("admin@srx>")
+///     .expect("set cli screen-length 0", "Screen length set to 0")
+///     .expect("show system uptime", "Current time: 2024-05-01 12:00:00 UTC");
+///
+/// let mut juniper = JuniperDevice::from_connection(mock)?;
+/// assert!(juniper.send_command("show system uptime")?.starts_with("Current time"));
+/// juniper.connection().assert_done();
+/// # Ok::<(), rustmiko::Error>(())
+/// ```
+///
 /// [`JuniperSSH`]: crate::devices::juniper::JuniperSSH
 /// [`JuniperTelnet`]: crate::devices::juniper::JuniperTelnet
-pub struct JuniperDevice<C: Connection> {
+/// [`MockConnection`]: crate::devices::generic::connection::MockConnection
+/// [`ReplayConnection`]: crate::devices::generic::connection::ReplayConnection
+pub struct JuniperDevice<C> {
     connection: C,
+    base_prompt: String,
     prompt_end: Regex,
+    /// Matches the prompt as well as the [`CONFIRM_EXIT`] question.
+    exit_prompt_end: Regex,
+    load_input: Regex,
+    /// Matches the [`LOAD_INPUT`] line anywhere in the output, or the prompt if the device
+    /// rejected the `load` command.
+    load_prompt_end: Regex,
+    error_patterns: ErrorPatterns,
+    edit_path: Option<String>,
+    /// Whether the last prompt was the one of the shell, where `cli` starts the CLI.
+    in_shell: bool,
 }
 
 impl<C: Connection<ConnectionHandler = C>> JuniperDevice<C> {
-    pub fn connect<A: ToSocketAddrs>(addr: A, username: &str, password: &str) -> Result<JuniperDevice<C>, Box<dyn Error>> {
-        let mut device = JuniperDevice {
-            connection: C::connect(addr, Some(username), Some(password))?,
-            prompt_end: Regex::new(r"[>#%]")?,
-        };
+    pub fn connect<A: ToSocketAddrs>(addr: A, username: &str, password: &str) -> Result<JuniperDevice<C>> {
+        Self::from_connection(C::connect(addr, Some(username), Some(password))?)
+    }
 
-        device.connection.read_ignore(&device.prompt_end);
-        Ok(device)
+    /// Connect to the device with all the settings of a [`ConnectionBuilder`], like timeouts or a
+    /// custom port.
+    pub fn connect_with(builder: &ConnectionBuilder) -> Result<JuniperDevice<C>> {
+        Self::from_connection(C::connect_with(builder)?)
     }
 
-    pub fn enter_cli(&mut self) -> io::Result<()> {
+    /// Start the CLI with `cli` when logged in to the shell, e.g. as `root`, and prepare the
+    /// session. Does nothing if the session already is in the CLI.
+    pub fn enter_cli(&mut self) -> Result<()> {
+        if !self.in_shell {
+            return Ok(());
+        }
         self.execute_raw("cli")?;
+        self.prepare_session()
+    }
+}
+
+impl<C: Connection> JuniperDevice<C> {
+    /// Wrap an already established connection and discover the prompt of the device.
+    pub fn from_connection(mut connection: C) -> Result<JuniperDevice<C>> {
+        let prompt = connection.find_prompt(&Regex::new("[>#%]")?)?;
+        let mut device = Self::new(connection, &prompt)?;
+        if !Self::is_shell_prompt(&prompt) {
+            device.prepare_session()?;
+        }
+        Ok(device)
+    }
+
+    /// Prepares the session for automation by turning off the pager with
+    /// `set cli screen-length 0`, so long output isn't interrupted. This runs automatically when
+    /// connecting, or after [`enter_cli`] when logged in to the shell.
+    ///
+    /// [`enter_cli`]: JuniperDevice::enter_cli
+    pub fn prepare_session(&mut self) -> Result<()> {
+        self.execute_raw("set cli screen-length 0")
+    }
+
+    /// Discover the prompt again and take over the user, hostname and mode from it. A hostname
+    /// changed by a commit through this device is noticed on its own, this is for changes it
+    /// can't see, e.g. after logging in as another user.
+    pub fn find_prompt(&mut self) -> Result<String> {
+        let prompt = self.connection.find_prompt(&Regex::new("[>#%]$")?)?;
+        self.set_prompt(&prompt)?;
+        Ok(prompt)
+    }
+
+    /// Get the committed configuration in the given format with `show configuration`, e.g. for a
+    /// backup. The `## Last commit` header and the CLI banner of the XML format are removed, so
+    /// only the configuration is returned. Works in operational and configuration mode.
+    pub fn get_config(&mut self, format: ConfigFormat) -> Result<String> {
+        let command = self.config_command(format);
+        let output = self.send_command(&command)?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Types the configuration into `load <action> terminal`.
+    fn load_terminal(&mut self, action: LoadAction, config: &str) -> Result<()> {
+        let command = action.command();
+        let line = format!("{}{}", command, self.connection.line_ending());
+        self.connection.write_raw(line.as_bytes())?;
+        let output = self.connection.read_until_pattern(&self.load_prompt_end)
+            .map_err(|e| e.with_command(command))?;
+        self.start_load(command, output)?;
+
+        let input = Self::input_for_load(config, self.connection.line_ending());
+        self.connection.write_raw(input.as_bytes())?;
+        let output = self.connection.read_until_prompt(&self.prompt_end)
+            .map_err(|e| e.with_command(command))?;
+        self.finish_load(command, config, output)
+    }
+}
+
+impl<C> JuniperDevice<C> {
+    fn new(connection: C, prompt: &str) -> Result<JuniperDevice<C>> {
+        let base_prompt = Self::base_prompt_of(prompt).to_string();
+
+        let prompt_end = Self::prompt_pattern(&base_prompt)?;
+
+        Ok(JuniperDevice {
+            connection,
+            exit_prompt_end: Self::exit_prompt_pattern(&prompt_end)?,
+            load_input: Regex::new(LOAD_INPUT)?,
+            load_prompt_end: Self::load_prompt_pattern(&prompt_end)?,
+            prompt_end,
+            base_prompt,
+            error_patterns: ErrorPatterns::new(ERROR_PATTERNS)?,
+            edit_path: None,
+            in_shell: Self::is_shell_prompt(prompt),
+        })
+    }
+
+    /// The prompt of the device without the mode suffix, usually `user@hostname`.
+    pub fn base_prompt(&self) -> &str {
+        &self.base_prompt
+    }
+
+    /// The hierarchy level in configuration mode as shown by the `[edit ...]` banner, e.g.
+    /// `interfaces ge-0/0/0`. It's empty at the top and `None` outside of configuration mode.
+    pub fn edit_path(&self) -> Option<&str> {
+        self.edit_path.as_deref()
+    }
+
+    /// The underlying connection, e.g. to check which SSH authentication method was used.
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// The underlying connection, e.g. to read the raw output of a command with
+    /// `send_command_raw`.
+    pub fn connection_mut(&mut self) -> &mut C {
+        &mut self.connection
+    }
+
+    /// The patterns which mark the output of a command as rejected by the device.
+    pub fn error_patterns(&self) -> &ErrorPatterns {
+        &self.error_patterns
+    }
+
+    /// Change the patterns which mark the output of a command as rejected by the device, e.g. to
+    /// add custom ones.
+    pub fn error_patterns_mut(&mut self) -> &mut ErrorPatterns {
+        &mut self.error_patterns
+    }
+
+    /// Takes over a new hostname from the prompt, and reads the hierarchy level from the
+    /// `[edit ...]` banner, which Junos shows above the prompt in configuration mode. The banner is
+    /// removed from the output, and so is the status of a virtual chassis or cluster member like
+    /// `{master:0}`, which Junos shows in front of the banner or on its own line above the prompt.
+    fn update_prompt(&mut self, output: String, prompt: &str) -> String {
+        if self.prompt_end.is_match(prompt) {
+            self.base_prompt = Self::base_prompt_of(prompt).to_string();
+            self.in_shell = Self::is_shell_prompt(prompt);
+        }
+
+        let in_config = prompt.ends_with('#');
+        if !in_config {
+            self.edit_path = None;
+        }
+
+        let mut lines: Vec<&str> = output.lines().collect();
+        let Some(&last) = lines.last() else {
+            return output;
+        };
+        let status = Self::strip_status(last);
+        match Self::edit_banner(status.unwrap_or(last)) {
+            Some(path) if in_config => self.edit_path = Some(path.to_string()),
+            _ if status.is_some_and(|rest| rest.trim().is_empty()) => {},
+            _ => return output,
+        }
+
+        lines.pop();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// The path in a banner like `[edit interfaces ge-0/0/0]`.
+    fn edit_banner(line: &str) -> Option<&str> {
+        let path = line.trim().strip_prefix("[edit")?.strip_suffix(']')?;
+        match path.is_empty() || path.starts_with(' ') {
+            true => Some(path.trim()),
+            false => None,
+        }
+    }
+
+    /// The rest of a line that starts with the status of a virtual chassis or cluster member, like
+    /// `{master:0}[edit]` or `{primary:node0}`.
+    fn strip_status(line: &str) -> Option<&str> {
+        let (status, rest) = line.trim().strip_prefix('{')?.split_once('}')?;
+        match !status.is_empty() && !status.contains(char::is_whitespace) {
+            true => Some(rest),
+            false => None,
+        }
+    }
+
+    fn describe_edit_path(path: Option<&str>) -> String {
+        match path {
+            None => "operational mode".to_string(),
+            Some("") => "[edit]".to_string(),
+            Some(path) => format!("[edit {}]", path),
+        }
+    }
+
+    /// Checks that the session is in configuration mode after `configure`.
+    fn check_config(&self) -> Result<()> {
+        if self.edit_path.is_none() {
+            return Err(Error::WrongMode {
+                command: "configure".to_string(),
+                expected: "configuration mode".to_string(),
+                actual: Self::describe_edit_path(None),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that a command, sent at the `previous` hierarchy level, went down the hierarchy.
+    fn check_sub_mode(&self, command: &str, previous: Option<&str>) -> Result<()> {
+        let entered = match (previous, self.edit_path.as_deref()) {
+            (Some(previous), Some(current)) => {
+                current != previous && (previous.is_empty() || current.starts_with(&format!("{} ", previous)))
+            },
+            _ => false,
+        };
+
+        if !entered {
+            return Err(Error::WrongMode {
+                command: command.to_string(),
+                expected: format!("a hierarchy level below {}", Self::describe_edit_path(previous)),
+                actual: Self::describe_edit_path(self.edit_path.as_deref()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the output of a commit or rollback contains the line that marks it as
+    /// successful, which Junos doesn't show when it fails for any reason.
+    fn check_success(command: &str, output: String, success: &str) -> Result<()> {
+        if !output.lines().any(|line| line.trim() == success) {
+            return Err(Error::CommandFailed {
+                command: command.to_string(),
+                output,
+            });
+        }
+        Ok(())
+    }
+
+    /// Builds `commit comment "<comment>"`, escaping quotes in the comment.
+    fn commit_comment_command(comment: &str) -> String {
+        format!("commit comment \"{}\"", comment.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// Handles the answer to `load ... terminal` and returns an error if the device didn't ask for
+    /// the input but went back to the prompt.
+    fn start_load(&mut self, command: &str, output: String) -> Result<()> {
+        if self.load_input.is_match(&output) {
+            return Ok(());
+        }
+
+        let prompt = last_line(&output).unwrap_or_default().to_string();
+        let output = strip_command_and_prompt(&output, command, &self.prompt_end);
+        let output = self.update_prompt(output, &prompt);
+        let output = self.error_patterns.check(command, output)?;
+        Err(Error::CommandFailed {
+            command: command.to_string(),
+            output,
+        })
+    }
+
+    /// The configuration to type after `load ... terminal`, ended with Ctrl-D on a new line.
+    fn input_for_load(config: &str, line_ending: &str) -> String {
+        let mut input = String::with_capacity(config.len() + config.lines().count() * line_ending.len() + 1);
+        for line in config.lines() {
+            input.push_str(line);
+            input.push_str(line_ending);
+        }
+        input.push(END_OF_INPUT);
+        input
+    }
+
+    /// Checks the output the device answered the load input with. The echo of the configuration is
+    /// removed, so only the syntax errors and the result are left in the returned error.
+    fn finish_load(&mut self, command: &str, config: &str, output: String) -> Result<()> {
+        let prompt = last_line(&output).unwrap_or_default().to_string();
+        let output = strip_command_and_prompt(&output, "", &self.prompt_end);
+        let output = self.update_prompt(output, &prompt);
+
+        let mut echo = config.lines().map(str::trim).filter(|line| !line.is_empty()).peekable();
+        let output = output.lines()
+            .filter(|line| {
+                let line = line.trim();
+                line.is_empty() || echo.next_if_eq(&line).is_none()
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let output = output.trim().to_string();
+
+        let output = self.error_patterns.check(command, output)?;
+        Self::check_success(command, output, LOAD_COMPLETE)
+    }
+
+    /// Builds `show configuration` for the format. In configuration mode it runs through `run`, as
+    /// `show` would only show the candidate configuration below the current hierarchy level.
+    fn config_command(&self, format: ConfigFormat) -> String {
+        match self.edit_path {
+            Some(_) => format!("run show configuration{}", format.pipe()),
+            None => format!("show configuration{}", format.pipe()),
+        }
+    }
+
+    /// Removes the `## Last commit` and `## Last changed` headers, the `<cli>` block with the banner
+    /// at the end of the XML format and the blank lines around the configuration.
+    fn clean_config(output: &str) -> String {
+        let mut lines = Vec::new();
+        let mut in_cli = false;
+        for line in output.lines() {
+            let trimmed = line.trim();
+            match trimmed {
+                "<cli>" => in_cli = true,
+                "</cli>" => in_cli = false,
+                _ if in_cli => {},
+                _ if trimmed.starts_with("## Last commit:") || trimmed.starts_with("## Last changed:") => {},
+                _ => lines.push(line.trim_end()),
+            }
+        }
+
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let start = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+        lines[start..].join("\n")
+    }
+
+    /// Whether the device answered with the [`CONFIRM_EXIT`] question instead of the prompt.
+    fn confirms_exit(&self, prompt: &str) -> bool {
+        !self.prompt_end.is_match(prompt)
+    }
+
+    /// Strips the mode suffix like `>`, `#` or the shell's `:RE:0%` from a prompt.
+    fn base_prompt_of(prompt: &str) -> &str {
+        let prompt = prompt.trim_end_matches(['>', '#', '%']);
+        match prompt.find(':') {
+            Some(index) => &prompt[..index],
+            None => prompt,
+        }
+    }
+
+    /// Whether the prompt belongs to the shell, like `root@srx:RE:0%`, where the CLI commands
+    /// aren't available.
+    fn is_shell_prompt(prompt: &str) -> bool {
+        prompt.ends_with('%')
+    }
+
+    /// Builds a prompt pattern for `user@hostname` that matches the shell, operational and
+    /// configuration mode, e.g. `root@srx:RE:0%`, `admin@srx>` or `admin@srx#`. Any hostname is
+    /// accepted for the user, as a commit can change it.
+    fn prompt_pattern(base_prompt: &str) -> Result<Regex> {
+        let base_prompt = match base_prompt.split_once('@') {
+            Some((user, _)) => format!(r"{}@[^\s:>#%]+", regex::escape(user)),
+            None => regex::escape(base_prompt),
+        };
+        Ok(Regex::new(&format!(r"^{}(:\S*)?[>#%]$", base_prompt))?)
+    }
+
+    /// The prompt or the [`CONFIRM_EXIT`] question.
+    fn exit_prompt_pattern(prompt_end: &Regex) -> Result<Regex> {
+        Ok(Regex::new(&format!("{}|{}", prompt_end.as_str(), CONFIRM_EXIT))?)
+    }
+
+    /// The [`LOAD_INPUT`] line anywhere in the output, or the prompt.
+    fn load_prompt_pattern(prompt_end: &Regex) -> Result<Regex> {
+        Ok(Regex::new(&format!("{}|(?m){}", LOAD_INPUT, prompt_end.as_str()))?)
+    }
+
+    /// Takes over the base prompt and the mode from a prompt of the device.
+    fn set_prompt(&mut self, prompt: &str) -> Result<()> {
+        self.base_prompt = Self::base_prompt_of(prompt).to_string();
+        self.prompt_end = Self::prompt_pattern(&self.base_prompt)?;
+        self.exit_prompt_end = Self::exit_prompt_pattern(&self.prompt_end)?;
+        self.load_prompt_end = Self::load_prompt_pattern(&self.prompt_end)?;
+        self.in_shell = Self::is_shell_prompt(prompt);
+        self.edit_path = match prompt.ends_with('#') {
+            true => self.edit_path.take().or(Some(String::new())),
+            false => None,
+        };
         Ok(())
     }
 }
@@ -40,32 +461,486 @@ impl<C: Connection<ConnectionHandler = C>> JuniperDevice<C> {
 impl<C: Connection> Configurable for JuniperDevice<C> {
     type SessionType = Self;
 
-    fn enter_config(&mut self) -> io::Result<ConfigurationMode<Self>> {
+    fn enter_config(&mut self) -> Result<ConfigurationMode<'_, Self>> {
         self.execute_raw("configure")?;
+        self.check_config()?;
         Ok(ConfigurationMode::enter(self))
     }
 
-    fn execute_raw(&mut self, command: &str) -> io::Result<()> {
-        self.connection.execute_raw(command, &self.prompt_end)
+    fn execute_raw(&mut self, command: &str) -> Result<()> {
+        self.send_command(command)?;
+        Ok(())
+    }
+
+    fn send_command(&mut self, command: &str) -> Result<String> {
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &self.prompt_end)?;
+        let output = self.update_prompt(output, &prompt);
+        self.error_patterns.check(command, output)
     }
 
-    fn exit(&mut self) -> io::Result<()> {
+    fn exit(&mut self) -> Result<()> {
         self.execute_raw("exit")
     }
+
+    fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+        let previous = self.edit_path.clone();
+        self.execute_raw(command)?;
+        self.check_sub_mode(command, previous.as_deref())
+    }
+
+    fn exit_config(&mut self) -> Result<()> {
+        if self.edit_path.is_none() {
+            return Ok(());
+        }
+
+        let command = "exit configuration-mode";
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &self.exit_prompt_end)?;
+        if self.confirms_exit(&prompt) {
+            // The candidate configuration stays around for the next configuration session.
+            warn!("Leaving the configuration mode with uncommitted changes");
+            return self.execute_raw("yes");
+        }
+        let output = self.update_prompt(output, &prompt);
+        self.error_patterns.check(command, output)?;
+        Ok(())
+    }
+
+    fn abort_config(&mut self) -> Result<()> {
+        if self.edit_path.is_none() {
+            return Ok(());
+        }
+        self.execute_raw("rollback 0")?;
+        self.exit_config()
+    }
 }
 
 impl<'a, C: Connection> InterfaceConfigurable for ConfigurationMode<'a, JuniperDevice<C>> {
-    fn interface_up(&mut self, interface: &Interface) -> io::Result<()> {
+    fn interface_up(&mut self, interface: &Interface) -> Result<()> {
         self.session.execute_raw(&format!("set interfaces {} enable", interface.name()))
     }
 
-    fn interface_down(&mut self, interface: &Interface) -> io::Result<()> {
+    fn interface_down(&mut self, interface: &Interface) -> Result<()> {
         self.session.execute_raw(&format!("set interfaces {} disable", interface.name()))
     }
 }
 
 impl<T: Connection> ConfigurationMode<'_, JuniperDevice<T>> {
-    pub fn commit(&mut self) -> io::Result<()> {
-        self.session.execute_raw("commit")
+    /// Activate the candidate configuration with `commit`. Fails if the device doesn't report
+    /// `commit complete`, e.g. because the configuration check failed.
+    ///
+    /// This also confirms an earlier [`commit_confirmed`].
+    ///
+    /// [`commit_confirmed`]: ConfigurationMode::commit_confirmed
+    pub fn commit(&mut self) -> Result<()> {
+        self.commit_with("commit", COMMIT_COMPLETE)
+    }
+
+    /// Commit with a comment, which shows up in `show system commit`.
+    pub fn commit_with_comment(&mut self, comment: &str) -> Result<()> {
+        self.commit_with(&JuniperDevice::<T>::commit_comment_command(comment), COMMIT_COMPLETE)
+    }
+
+    /// Check the candidate configuration with `commit check` without activating it.
+    pub fn commit_check(&mut self) -> Result<()> {
+        self.commit_with("commit check", CHECK_SUCCEEDS)
+    }
+
+    /// Activate the candidate configuration with `commit confirmed <minutes>`. The device rolls
+    /// back to the previous configuration by itself unless another [`commit`] follows within the
+    /// given minutes, so a change that cuts off the connection reverts itself.
+    ///
+    /// [`commit`]: ConfigurationMode::commit
+    pub fn commit_confirmed(&mut self, minutes: u32) -> Result<()> {
+        self.commit_with(&format!("commit confirmed {}", minutes), COMMIT_COMPLETE)
+    }
+
+    /// Commit on both routing engines with `commit synchronize`.
+    pub fn commit_synchronize(&mut self) -> Result<()> {
+        self.commit_with("commit synchronize", COMMIT_COMPLETE)
+    }
+
+    /// Commit and leave the configuration mode with `commit and-quit`. If the commit fails, the
+    /// session is still in configuration mode until the guard is dropped.
+    pub fn commit_and_quit(mut self) -> Result<()> {
+        self.commit_with("commit and-quit", COMMIT_COMPLETE)?;
+        self.finish()
+    }
+
+    /// Replace the candidate configuration with a previously committed one using
+    /// `rollback <number>`, where 0 is the active configuration. It still needs to be committed.
+    pub fn rollback(&mut self, number: u32) -> Result<()> {
+        self.commit_with(&format!("rollback {}", number), LOAD_COMPLETE)
+    }
+
+    /// Compare the candidate with the active configuration using `show | compare`, to review what
+    /// a commit would change.
+    pub fn diff(&mut self) -> Result<ConfigDiff> {
+        let output = self.session.send_command("show | compare")?;
+        Ok(ConfigDiff::parse(&output))
     }
-}
\ No newline at end of file
+
+    /// Load a configuration into the candidate configuration with `load <action> terminal`.
+    /// Syntax errors in the configuration are returned as [`Error::CommandFailed`]. The loaded
+    /// configuration still needs to be committed.
+    pub fn load(&mut self, action: LoadAction, config: &str) -> Result<()> {
+        self.session.load_terminal(action, config)
+    }
+
+    /// Load a configuration from a local file like [`ConfigurationMode::load`].
+    pub fn load_file<P: AsRef<Path>>(&mut self, action: LoadAction, path: P) -> Result<()> {
+        let config = std::fs::read_to_string(path)?;
+        self.load(action, &config)
+    }
+
+    /// Merge a configuration in the curly brace format with `load merge terminal`.
+    pub fn load_merge(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Merge, config)
+    }
+
+    /// Load a configuration in the curly brace format with `load replace terminal`, where the
+    /// levels tagged with `replace:` replace the existing ones.
+    pub fn load_replace(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Replace, config)
+    }
+
+    /// Replace the whole candidate configuration with one in the curly brace format using
+    /// `load override terminal`.
+    pub fn load_override(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Override, config)
+    }
+
+    /// Run configuration commands like `set` and `delete`, one per line, with
+    /// `load set terminal`.
+    pub fn load_set(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Set, config)
+    }
+
+    fn commit_with(&mut self, command: &str, success: &str) -> Result<()> {
+        let output = self.session.send_command(command)?;
+        JuniperDevice::<T>::check_success(command, output, success)
+    }
+
+    /// Move down the hierarchy with `edit <path>`, e.g. `protocols ospf area 0`. The returned guard
+    /// goes back up when dropped.
+    pub fn edit(&mut self, path: &str) -> Result<EditMode<'_, JuniperDevice<T>>> {
+        self.sub_mode(&format!("edit {}", path))
+    }
+
+    /// Move to the configuration of an interface with `edit interfaces <name>`.
+    pub fn interface(&mut self, interface: &Interface) -> Result<InterfaceMode<'_, JuniperDevice<T>>> {
+        self.edit(&format!("interfaces {}", interface.name()))
+    }
+}
+
+impl<T: Connection> SubMode<'_, JuniperDevice<T>> {
+    /// Move further down the hierarchy with `edit <path>`, relative to this level.
+    pub fn edit(&mut self, path: &str) -> Result<EditMode<'_, JuniperDevice<T>>> {
+        self.sub_mode(&format!("edit {}", path))
+    }
+}
+#[cfg(feature = "async")]
+impl<C: AsyncConnection<ConnectionHandler = C>> JuniperDevice<C> {
+    pub async fn connect_async<A: ToSocketAddrs + Send>(addr: A, username: &str, password: &str) -> Result<JuniperDevice<C>> {
+        Self::from_async_connection(C::connect(addr, Some(username), Some(password)).await?).await
+    }
+
+    /// Connect to the device with all the settings of a [`ConnectionBuilder`], like timeouts or a
+    /// custom port.
+    pub async fn connect_with_async(builder: &ConnectionBuilder) -> Result<JuniperDevice<C>> {
+        Self::from_async_connection(C::connect_with(builder).await?).await
+    }
+
+    /// Start the CLI like [`JuniperDevice::enter_cli`].
+    pub async fn enter_cli_async(&mut self) -> Result<()> {
+        if !self.in_shell {
+            return Ok(());
+        }
+        AsyncConfigurable::execute_raw(self, "cli").await?;
+        self.prepare_session_async().await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<C: AsyncConnection> JuniperDevice<C> {
+    /// Wrap an already established async connection and discover the prompt of the device.
+    pub async fn from_async_connection(mut connection: C) -> Result<JuniperDevice<C>> {
+        let prompt = connection.find_prompt(&Regex::new("[>#%]")?).await?;
+        let mut device = Self::new(connection, &prompt)?;
+        if !Self::is_shell_prompt(&prompt) {
+            device.prepare_session_async().await?;
+        }
+        Ok(device)
+    }
+
+    /// Prepares the session like [`JuniperDevice::prepare_session`]. This runs automatically when
+    /// connecting, or after [`enter_cli_async`] when logged in to the shell.
+    ///
+    /// [`enter_cli_async`]: JuniperDevice::enter_cli_async
+    pub async fn prepare_session_async(&mut self) -> Result<()> {
+        AsyncConfigurable::execute_raw(self, "set cli screen-length 0").await
+    }
+
+    /// Discover the prompt again like [`JuniperDevice::find_prompt`].
+    pub async fn find_prompt_async(&mut self) -> Result<String> {
+        let prompt = self.connection.find_prompt(&Regex::new("[>#%]$")?).await?;
+        self.set_prompt(&prompt)?;
+        Ok(prompt)
+    }
+
+    /// Get the committed configuration in the given format, like [`JuniperDevice::get_config`].
+    pub async fn get_config_async(&mut self, format: ConfigFormat) -> Result<String> {
+        let command = self.config_command(format);
+        let output = AsyncConfigurable::send_command(self, &command).await?;
+        Ok(Self::clean_config(&output))
+    }
+
+    /// Types the configuration into `load <action> terminal`.
+    async fn load_terminal_async(&mut self, action: LoadAction, config: &str) -> Result<()> {
+        let command = action.command();
+        let line = format!("{}{}", command, self.connection.line_ending());
+        self.connection.write_raw(line.as_bytes()).await?;
+        let output = self.connection.read_until_pattern(&self.load_prompt_end).await
+            .map_err(|e| e.with_command(command))?;
+        self.start_load(command, output)?;
+
+        let input = Self::input_for_load(config, self.connection.line_ending());
+        self.connection.write_raw(input.as_bytes()).await?;
+        let output = self.connection.read_until_prompt(&self.prompt_end).await
+            .map_err(|e| e.with_command(command))?;
+        self.finish_load(command, config, output)
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<C: AsyncConnection> AsyncConfigurable for JuniperDevice<C> {
+    type SessionType = Self;
+
+    async fn enter_config(&mut self) -> Result<AsyncConfigurationMode<'_, Self>> {
+        self.execute_raw("configure").await?;
+        self.check_config()?;
+        Ok(AsyncConfigurationMode::enter(self))
+    }
+
+    async fn execute_raw(&mut self, command: &str) -> Result<()> {
+        self.send_command(command).await?;
+        Ok(())
+    }
+
+    async fn send_command(&mut self, command: &str) -> Result<String> {
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &self.prompt_end).await?;
+        let output = self.update_prompt(output, &prompt);
+        self.error_patterns.check(command, output)
+    }
+
+    async fn exit(&mut self) -> Result<()> {
+        self.execute_raw("exit").await
+    }
+
+    async fn enter_sub_mode(&mut self, command: &str) -> Result<()> {
+        let previous = self.edit_path.clone();
+        self.execute_raw(command).await?;
+        self.check_sub_mode(command, previous.as_deref())
+    }
+
+    async fn exit_config(&mut self) -> Result<()> {
+        if self.edit_path.is_none() {
+            return Ok(());
+        }
+
+        let command = "exit configuration-mode";
+        let (output, prompt) = self.connection.send_command_with_prompt(command, &self.exit_prompt_end).await?;
+        if self.confirms_exit(&prompt) {
+            warn!("Leaving the configuration mode with uncommitted changes");
+            return self.execute_raw("yes").await;
+        }
+        let output = self.update_prompt(output, &prompt);
+        self.error_patterns.check(command, output)?;
+        Ok(())
+    }
+
+    async fn abort_config(&mut self) -> Result<()> {
+        if self.edit_path.is_none() {
+            return Ok(());
+        }
+        self.execute_raw("rollback 0").await?;
+        self.exit_config().await
+    }
+}
+
+#[cfg(feature = "async")]
+#[async_trait]
+impl<'a, C: AsyncConnection> AsyncInterfaceConfigurable for AsyncConfigurationMode<'a, JuniperDevice<C>> {
+    async fn interface_up(&mut self, interface: &Interface) -> Result<()> {
+        self.session.execute_raw(&format!("set interfaces {} enable", interface.name())).await
+    }
+
+    async fn interface_down(&mut self, interface: &Interface) -> Result<()> {
+        self.session.execute_raw(&format!("set interfaces {} disable", interface.name())).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncConnection> AsyncConfigurationMode<'_, JuniperDevice<T>> {
+    /// Activate the candidate configuration like [`ConfigurationMode::commit`].
+    pub async fn commit(&mut self) -> Result<()> {
+        self.commit_with("commit", COMMIT_COMPLETE).await
+    }
+
+    /// Commit with a comment like [`ConfigurationMode::commit_with_comment`].
+    pub async fn commit_with_comment(&mut self, comment: &str) -> Result<()> {
+        self.commit_with(&JuniperDevice::<T>::commit_comment_command(comment), COMMIT_COMPLETE).await
+    }
+
+    /// Check the candidate configuration like [`ConfigurationMode::commit_check`].
+    pub async fn commit_check(&mut self) -> Result<()> {
+        self.commit_with("commit check", CHECK_SUCCEEDS).await
+    }
+
+    /// Commit with an automatic rollback like [`ConfigurationMode::commit_confirmed`].
+    pub async fn commit_confirmed(&mut self, minutes: u32) -> Result<()> {
+        self.commit_with(&format!("commit confirmed {}", minutes), COMMIT_COMPLETE).await
+    }
+
+    /// Commit on both routing engines like [`ConfigurationMode::commit_synchronize`].
+    pub async fn commit_synchronize(&mut self) -> Result<()> {
+        self.commit_with("commit synchronize", COMMIT_COMPLETE).await
+    }
+
+    /// Commit and leave the configuration mode like [`ConfigurationMode::commit_and_quit`].
+    pub async fn commit_and_quit(mut self) -> Result<()> {
+        self.commit_with("commit and-quit", COMMIT_COMPLETE).await?;
+        self.finish().await
+    }
+
+    /// Load a previously committed configuration like [`ConfigurationMode::rollback`].
+    pub async fn rollback(&mut self, number: u32) -> Result<()> {
+        self.commit_with(&format!("rollback {}", number), LOAD_COMPLETE).await
+    }
+
+    /// Compare the candidate with the active configuration like [`ConfigurationMode::diff`].
+    pub async fn diff(&mut self) -> Result<ConfigDiff> {
+        let output = self.session.send_command("show | compare").await?;
+        Ok(ConfigDiff::parse(&output))
+    }
+
+    /// Load a configuration like [`ConfigurationMode::load`].
+    pub async fn load(&mut self, action: LoadAction, config: &str) -> Result<()> {
+        self.session.load_terminal_async(action, config).await
+    }
+
+    /// Load a configuration from a local file like [`ConfigurationMode::load_file`].
+    pub async fn load_file<P: AsRef<Path>>(&mut self, action: LoadAction, path: P) -> Result<()> {
+        let config = tokio::fs::read_to_string(path).await?;
+        self.load(action, &config).await
+    }
+
+    /// Merge a configuration like [`ConfigurationMode::load_merge`].
+    pub async fn load_merge(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Merge, config).await
+    }
+
+    /// Replace tagged levels like [`ConfigurationMode::load_replace`].
+    pub async fn load_replace(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Replace, config).await
+    }
+
+    /// Replace the whole candidate configuration like [`ConfigurationMode::load_override`].
+    pub async fn load_override(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Override, config).await
+    }
+
+    /// Run configuration commands like [`ConfigurationMode::load_set`].
+    pub async fn load_set(&mut self, config: &str) -> Result<()> {
+        self.load(LoadAction::Set, config).await
+    }
+
+    async fn commit_with(&mut self, command: &str, success: &str) -> Result<()> {
+        let output = self.session.send_command(command).await?;
+        JuniperDevice::<T>::check_success(command, output, success)
+    }
+
+    /// Move down the hierarchy like [`ConfigurationMode::edit`].
+    pub async fn edit(&mut self, path: &str) -> Result<AsyncSubMode<'_, JuniperDevice<T>>> {
+        self.sub_mode(&format!("edit {}", path)).await
+    }
+
+    /// Move to the configuration of an interface like [`ConfigurationMode::interface`].
+    pub async fn interface(&mut self, interface: &Interface) -> Result<AsyncSubMode<'_, JuniperDevice<T>>> {
+        self.edit(&format!("interfaces {}", interface.name())).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncConnection> AsyncSubMode<'_, JuniperDevice<T>> {
+    /// Move further down the hierarchy with `edit <path>`, relative to this level.
+    pub async fn edit(&mut self, path: &str) -> Result<AsyncSubMode<'_, JuniperDevice<T>>> {
+        self.sub_mode(&format!("edit {}", path)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::devices::generic::connection::MockConnection;
+    use crate::devices::generic::device_types::config::Configurable;
+    use crate::devices::juniper::{ConfigFormat, JuniperDevice};
+
+    /// A mock of a virtual chassis member, which shows its status above every prompt.
+    fn mock(prompt: &str) -> MockConnection {
+        MockConnection::new(prompt).expect("set cli screen-length 0", "Screen length set to 0\r\n\r\n{master:0}")
+    }
+
+    #[test]
+    fn removes_the_status_line_from_the_output() {
+        let mock = mock("admin@ex>")
+            .expect("show system uptime", "Current time: 2024-05-01 12:00:00 UTC\r\nSystem booted: 2024-04-01 12:00:00 UTC (4w2d 00:00 ago)\r\n\r\n{master:0}");
+        let mut juniper = JuniperDevice::from_connection(mock).unwrap();
+
+        let output = juniper.send_command("show system uptime").unwrap();
+        assert_eq!(output, "Current time: 2024-05-01 12:00:00 UTC\nSystem booted: 2024-04-01 12:00:00 UTC (4w2d 00:00 ago)");
+        assert_eq!(juniper.edit_path(), None);
+        juniper.connection().assert_done();
+    }
+
+    #[test]
+    fn reads_the_edit_banner_behind_the_status() {
+        let mock = mock("admin@ex>")
+            .expect_prompt("configure", "Entering configuration mode\r\n\r\n{master:0}[edit]", "admin@ex#")
+            .expect("edit interfaces ge-0/0/0", "\r\n{master:0}[edit interfaces ge-0/0/0]")
+            .expect("set description uplink", "\r\n{master:0}[edit interfaces ge-0/0/0]")
+            .expect("exit", "\r\n{master:0}[edit]")
+            .expect_prompt("exit configuration-mode", "Exiting configuration mode\r\n\r\n{master:0}", "admin@ex>");
+        let mut juniper = JuniperDevice::from_connection(mock).unwrap();
+
+        let mut config = juniper.enter_config().unwrap();
+        assert_eq!(config.session.edit_path(), Some(""));
+        let mut interface = config.edit("interfaces ge-0/0/0").unwrap();
+        assert_eq!(interface.session.edit_path(), Some("interfaces ge-0/0/0"));
+        assert_eq!(interface.send_command("set description uplink").unwrap(), "");
+        interface.exit().unwrap();
+        assert_eq!(config.session.edit_path(), Some(""));
+        config.finish().unwrap();
+
+        assert_eq!(juniper.edit_path(), None);
+        juniper.connection().assert_done();
+    }
+
+    #[test]
+    fn removes_the_cluster_status_from_the_configuration() {
+        let mock = MockConnection::new("admin@srx>")
+            .expect("set cli screen-length 0", "Screen length set to 0\r\n\r\n{primary:node0}")
+            .expect("show configuration", "## Last commit: 2024-05-01 12:00:00 UTC by admin\r\nsystem {\r\n    host-name srx;\r\n}\r\n\r\n{primary:node0}");
+        let mut juniper = JuniperDevice::from_connection(mock).unwrap();
+
+        assert_eq!(juniper.get_config(ConfigFormat::Text).unwrap(), "system {\n    host-name srx;\n}");
+    }
+
+    #[test]
+    fn keeps_braces_of_the_configuration() {
+        let mock = mock("admin@ex>")
+            .expect("show configuration system services", "ssh;\r\nnetconf {\r\n    ssh;\r\n}");
+        let mut juniper = JuniperDevice::from_connection(mock).unwrap();
+
+        assert_eq!(juniper.send_command("show configuration system services").unwrap(), "ssh;\nnetconf {\n    ssh;\n}");
+    }
+}
@@ -0,0 +1,26 @@
+/// How `load` combines a configuration with the candidate configuration on Junos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadAction {
+    /// `load merge`: adds the statements in the curly brace format to the candidate configuration.
+    Merge,
+    /// `load replace`: like [`LoadAction::Merge`], but the hierarchy levels tagged with `replace:`
+    /// replace the existing ones.
+    Replace,
+    /// `load override`: replaces the whole candidate configuration with the one in the curly brace
+    /// format.
+    Override,
+    /// `load set`: runs configuration commands like `set` and `delete`, one per line.
+    Set,
+}
+
+impl LoadAction {
+    /// The command which reads the configuration from the terminal, e.g. `load merge terminal`.
+    pub fn command(&self) -> &'static str {
+        match self {
+            LoadAction::Merge => "load merge terminal",
+            LoadAction::Replace => "load replace terminal",
+            LoadAction::Override => "load override terminal",
+            LoadAction::Set => "load set terminal",
+        }
+    }
+}
@@ -1,7 +1,22 @@
+mod diff;
+mod format;
 mod juniper_api;
+mod load;
+
+pub use diff::{ConfigDiff, DiffLine};
+pub use format::ConfigFormat;
+pub use juniper_api::JuniperDevice;
+pub use load::LoadAction;
 
 use crate::devices::generic::connection::{SSHConnection, TelnetConnection};
-use crate::devices::juniper::juniper_api::JuniperDevice;
 
 pub type JuniperSSH = JuniperDevice<SSHConnection>;
 pub type JuniperTelnet = JuniperDevice<TelnetConnection>;
+
+#[cfg(feature = "async")]
+use crate::devices::generic::connection::{AsyncSSHConnection, AsyncTelnetConnection};
+
+#[cfg(feature = "async")]
+pub type AsyncJuniperSSH = JuniperDevice<AsyncSSHConnection>;
+#[cfg(feature = "async")]
+pub type AsyncJuniperTelnet = JuniperDevice<AsyncTelnetConnection>;
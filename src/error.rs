@@ -0,0 +1,121 @@
+//! The error type shared by all connections and devices.
+//!
+//! Every fallible function in rustmiko returns a [`Result`] with the [`Error`] enum, so callers can
+//! tell apart failures of the transport, the authentication and the commands sent to the device.
+
+use std::io;
+
+/// A convenience alias for results returned by rustmiko.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// All the errors that can happen while talking to a device.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Reading from or writing to the underlying stream failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The SSH library reported an error.
+    #[error("SSH error: {0}")]
+    Ssh(#[from] ssh2::Error),
+    /// The telnet library reported an error.
+    #[error("Telnet error: {0}")]
+    Telnet(#[from] telnet::TelnetError),
+    /// A prompt or error pattern couldn't be compiled.
+    #[error("Invalid pattern: {0}")]
+    Pattern(#[from] regex::Error),
+    /// The address couldn't be resolved to any socket address.
+    #[error("No socket address was supplied in addr")]
+    NoAddress,
+    /// The device didn't accept the credentials.
+    #[error("Authentication failed: {message}")]
+    Authentication {
+        message: String,
+        #[source]
+        source: Option<ssh2::Error>,
+    },
+    /// The host key of the SSH server doesn't match the known or pinned one.
+    #[error("The host key of {host} doesn't match the expected one, got {fingerprint}")]
+    HostKeyMismatch {
+        host: String,
+        fingerprint: String,
+    },
+    /// The SSH server isn't in the known hosts file and unknown hosts are rejected.
+    #[error("The host key of {host} ({fingerprint}) isn't known")]
+    HostKeyUnknown {
+        host: String,
+        fingerprint: String,
+    },
+    /// The device didn't send the expected prompt in time.
+    #[error("Timed out waiting for the prompt after `{command}`")]
+    Timeout {
+        command: String,
+        output: String,
+    },
+    /// The device answered with something that isn't the expected prompt.
+    #[error("Expected a prompt matching `{expected}`, but got `{output}`")]
+    PromptMismatch {
+        expected: String,
+        output: String,
+    },
+    /// The device rejected the command, e.g. with `% Invalid input detected`.
+    #[error("The device rejected `{command}`: {output}")]
+    CommandFailed {
+        command: String,
+        output: String,
+    },
+    /// The device isn't in the mode a command needs, or didn't end up in the mode a command
+    /// should have led to, e.g. after `enable` with a wrong secret.
+    #[error("Expected the device to be in {expected} for `{command}`, but it is in {actual}")]
+    WrongMode {
+        command: String,
+        expected: String,
+        actual: String,
+    },
+    /// A [`MockConnection`] received a command it wasn't scripted to expect.
+    ///
+    /// [`MockConnection`]: crate::devices::generic::connection::MockConnection
+    #[error("Unexpected command `{command}`, expected {}", expected.as_deref().map_or("no more commands".to_string(), |expected| format!("`{expected}`")))]
+    UnexpectedCommand {
+        command: String,
+        expected: Option<String>,
+    },
+}
+
+impl Error {
+    /// Creates an authentication error without an underlying SSH error.
+    pub(crate) fn authentication<S: Into<String>>(message: S) -> Error {
+        Error::Authentication {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Attaches the command that was sent to errors which carry one.
+    pub(crate) fn with_command(self, command: &str) -> Error {
+        match self {
+            Error::Timeout { output, .. } => Error::Timeout { command: command.to_string(), output },
+            error => error,
+        }
+    }
+
+    /// The output the device sent before the error happened, if any.
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            Error::Timeout { output, .. }
+            | Error::PromptMismatch { output, .. }
+            | Error::CommandFailed { output, .. } => Some(output),
+            _ => None,
+        }
+    }
+
+    /// The command that caused the error, if any.
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            Error::Timeout { command, .. }
+            | Error::CommandFailed { command, .. }
+            | Error::WrongMode { command, .. }
+            | Error::UnexpectedCommand { command, .. } => Some(command),
+            _ => None,
+        }
+    }
+}
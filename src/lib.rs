@@ -6,35 +6,39 @@
 //!
 //! Example of usage (Cisco Catalyst 2960 Switch):
 //! ```ignore
-//! fn main() -> anyhow::Result<()> {
-//! 	let mut cisco = match CiscoTelnet::connect("192.168.1.101:23", "admin", "admin") {
-//! 		Ok(cisco) => {
-//! 			println!("Connected successfully");
-//! 			cisco
-//! 		},
-//! 		Err(e) => {
-//! 			eprintln!("Failed to connect: {}", e);
-//! 			return Ok(());
-//! 		},
-//! 	};
+//! fn main() -> rustmiko::Result<()> {
+//!     let mut cisco = match CiscoTelnet::connect("192.168.1.101:23", "admin", "admin") {
+//!         Ok(cisco) => {
+//!             println!("Connected successfully");
+//!             cisco
+//!         },
+//!         Err(e) => {
+//!             eprintln!("Failed to connect: {}", e);
+//!             return Ok(());
+//!         },
+//!     };
 //!
-//! 	{
-//! 		let mut config = cisco.enter_config()?;
-//! 		for index in 1..=8 {
-//! 			let interface = config.get_interface("gi", &[0, index]);
-//! 			match config.interface_up(&interface) {
-//!                 Ok(_) => println!("Interface {} is now up", interface.name()),
-//!                 Err(_) => println!("Failed to set Interface {} up", interface.name())
-//!             }
-//! 		}
-//! 	}
+//!     let mut config = cisco.enter_config()?;
+//!     for index in 1..=8 {
+//!         let interface = config.get_interface("gi", &[0, index]);
+//!         match config.interface_up(&interface) {
+//!             Ok(_) => println!("Interface {} is now up", interface.name()),
+//!             Err(_) => println!("Failed to set Interface {} up", interface.name())
+//!         }
+//!     }
+//!     config.finish()?;
 //!
-//! 	if let Err(e) = cisco.save() {
-//! 		eprintln!("Failed to save configuration: {e}");
-//! 	}
+//!     if let Err(e) = cisco.save() {
+//!         eprintln!("Failed to save configuration: {e}");
+//!     }
 //!
-//! 	Ok(())
+//!     Ok(())
 //! }
 //! ```
 
-pub mod devices;
\ No newline at end of file
+pub mod devices;
+pub mod error;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+
+pub use error::{Error, Result};
\ No newline at end of file
@@ -0,0 +1,714 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use crate::simulator::{match_keyword, Cli, Keyword, Response, DEFAULT_SCREEN_LENGTH};
+
+/// Interface types and whether they can be created in the configuration mode.
+const INTERFACE_TYPES: &[(&str, bool)] = &[
+	("GigabitEthernet", false),
+	("FastEthernet", false),
+	("TenGigabitEthernet", false),
+	("Vlan", true),
+	("Loopback", true),
+	("Port-channel", true),
+	("Tunnel", true),
+];
+
+/// Commands of the configuration sub-modes which only have a single value, so setting them
+/// replaces the previous one.
+const SINGLE_VALUED: &[&str] = &["description", "speed", "duplex", "mtu", "name", "router-id", "password", "exec-timeout", "path", "maximum"];
+
+const GLOBAL_COMMANDS: &[&str] = &[
+	"aaa", "archive", "boot", "clock", "do", "enable", "end", "errdisable", "exit", "hostname", "interface", "ip", "ipv6",
+	"line", "logging", "no", "ntp", "router", "service", "snmp-server", "spanning-tree", "username", "vlan", "vtp",
+];
+const INTERFACE_COMMANDS: &[&str] = &[
+	"cdp", "channel-group", "description", "do", "duplex", "end", "exit", "ip", "ipv6", "mtu", "no", "power", "shutdown",
+	"spanning-tree", "speed", "storm-control", "switchport",
+];
+const VLAN_COMMANDS: &[&str] = &["do", "end", "exit", "name", "no", "shutdown", "state"];
+const ROUTER_COMMANDS: &[&str] = &[
+	"auto-summary", "default-information", "do", "end", "exit", "neighbor", "network", "no", "passive-interface",
+	"redistribute", "router-id",
+];
+const LINE_COMMANDS: &[&str] = &["access-class", "do", "end", "exec-timeout", "exit", "length", "logging", "login", "no", "password", "privilege", "transport"];
+const ARCHIVE_COMMANDS: &[&str] = &["do", "end", "exit", "maximum", "no", "path", "time-period", "write-memory"];
+
+/// How many archive files are kept if `maximum` isn't configured.
+const DEFAULT_ARCHIVE_MAXIMUM: usize = 10;
+
+/// A configuration line, with the indented lines below it.
+#[derive(Clone)]
+struct Section {
+	header: String,
+	children: Vec<String>,
+}
+
+impl Section {
+	fn new<S: Into<String>>(header: S, children: &[&str]) -> Section {
+		Section {
+			header: header.into(),
+			children: children.iter().map(|child| child.to_string()).collect(),
+		}
+	}
+}
+
+/// The configuration of a simulated Cisco IOS device.
+pub(crate) struct CiscoState {
+	hostname: String,
+	enable_secret: Option<String>,
+	running: Vec<Section>,
+	startup: Vec<Section>,
+	/// The configurations saved with `archive config`, the oldest first.
+	archives: Vec<(String, Vec<Section>)>,
+	/// How many archive files were written, which numbers the next one.
+	archive_count: usize,
+	/// The configuration to go back to when the timer of `configure terminal revert timer` or
+	/// `configure replace ... time` runs out.
+	revert: Option<(Instant, Vec<Section>)>,
+}
+
+impl CiscoState {
+	pub(crate) fn new(hostname: &str, enable_secret: Option<String>) -> CiscoState {
+		let mut running = vec![
+			Section::new("no service pad", &[]),
+			Section::new("spanning-tree mode pvst", &[]),
+		];
+		for index in 1..=8 {
+			running.push(Section::new(format!("interface GigabitEthernet0/{}", index), &[]));
+		}
+		running.push(Section::new("interface Vlan1", &["no ip address"]));
+		running.push(Section::new("line con 0", &[]));
+		running.push(Section::new("line vty 0 4", &["login"]));
+
+		CiscoState {
+			hostname: hostname.to_string(),
+			enable_secret,
+			startup: running.clone(),
+			running,
+			archives: Vec::new(),
+			archive_count: 0,
+			revert: None,
+		}
+	}
+
+	/// A setting of the `archive` section, like `path`.
+	fn archive_setting(&self, setting: &str) -> Option<String> {
+		let section = self.running.iter().find(|section| section.header == "archive")?;
+		section.children.iter().find_map(|child| {
+			child.strip_prefix(setting).and_then(|value| value.strip_prefix(' ')).map(String::from)
+		})
+	}
+
+	/// Saves the running configuration to the next archive file like `archive config` and returns
+	/// its name.
+	fn archive(&mut self) -> Result<String, String> {
+		let Some(path) = self.archive_setting("path") else {
+			return Err("%Error: Archive path is not configured".to_string());
+		};
+		let maximum = self.archive_setting("maximum")
+			.and_then(|maximum| maximum.parse().ok())
+			.unwrap_or(DEFAULT_ARCHIVE_MAXIMUM);
+
+		self.archive_count += 1;
+		let name = format!("{}-{}", path, self.archive_count);
+		self.archives.push((name.clone(), self.running.clone()));
+		if self.archives.len() > maximum {
+			self.archives.remove(0);
+		}
+		Ok(name)
+	}
+
+	/// A configuration file that `configure replace` can read.
+	fn file(&self, name: &str) -> Option<Vec<Section>> {
+		if matches!(name, "nvram:startup-config" | "startup-config") {
+			return Some(self.startup.clone());
+		}
+		self.archives.iter()
+			.find(|(archive, _)| archive == name)
+			.map(|(_, sections)| sections.clone())
+	}
+
+	/// Replaces the running configuration and returns the commands that did it.
+	fn replace(&mut self, target: Vec<Section>) -> Vec<String> {
+		let mut commands = Vec::new();
+		for section in self.running.iter().filter(|section| !target.iter().any(|target| target.header == section.header)) {
+			commands.push(format!("no {}", section.header));
+		}
+		for section in target.iter() {
+			let existing = self.running.iter().find(|existing| existing.header == section.header);
+			if existing.is_some_and(|existing| existing.children == section.children) {
+				continue;
+			}
+			commands.push(section.header.clone());
+			commands.extend(section.children.iter().map(|child| format!(" {}", child)));
+		}
+		self.running = target;
+		commands
+	}
+
+	/// Goes back to the saved configuration if the revert timer ran out.
+	fn check_revert_timer(&mut self) {
+		if self.revert.as_ref().is_some_and(|(deadline, _)| Instant::now() >= *deadline) {
+			if let Some((_, sections)) = self.revert.take() {
+				self.running = sections;
+			}
+		}
+	}
+
+	fn section_mut(&mut self, header: &str) -> Option<&mut Section> {
+		self.running.iter_mut().find(|section| section.header == header)
+	}
+
+	/// Renders a configuration like `show running-config` does.
+	fn render(&self, sections: &[Section]) -> Vec<String> {
+		let mut lines = vec!["!".to_string(), "version 15.2".to_string(), format!("hostname {}", self.hostname)];
+
+		let mut after_block = false;
+		for section in sections {
+			let block = !section.children.is_empty() || section.header.starts_with("interface ") || section.header.starts_with("line ");
+			if block || after_block {
+				lines.push("!".to_string());
+			}
+			lines.push(section.header.clone());
+			lines.extend(section.children.iter().map(|child| format!(" {}", child)));
+			after_block = block;
+		}
+
+		lines.push("!".to_string());
+		lines.push("end".to_string());
+		lines
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SubMode {
+	Interface,
+	Vlan,
+	Router,
+	Line,
+	Archive,
+}
+
+impl SubMode {
+	fn prompt(self) -> &'static str {
+		match self {
+			SubMode::Interface => "config-if",
+			SubMode::Vlan => "config-vlan",
+			SubMode::Router => "config-router",
+			SubMode::Line => "config-line",
+			SubMode::Archive => "config-archive",
+		}
+	}
+
+	fn commands(self) -> &'static [&'static str] {
+		match self {
+			SubMode::Interface => INTERFACE_COMMANDS,
+			SubMode::Vlan => VLAN_COMMANDS,
+			SubMode::Router => ROUTER_COMMANDS,
+			SubMode::Line => LINE_COMMANDS,
+			SubMode::Archive => ARCHIVE_COMMANDS,
+		}
+	}
+}
+
+#[derive(Clone, PartialEq)]
+enum Mode {
+	UserExec,
+	PrivilegedExec,
+	Config,
+	Sub(SubMode, String),
+}
+
+/// The CLI of a simulated Cisco IOS device.
+pub(crate) struct CiscoCli {
+	state: Arc<Mutex<CiscoState>>,
+	mode: Mode,
+	awaiting_secret: bool,
+	screen_length: usize,
+}
+
+impl CiscoCli {
+	pub(crate) fn new(state: Arc<Mutex<CiscoState>>) -> CiscoCli {
+		CiscoCli {
+			state,
+			mode: Mode::UserExec,
+			awaiting_secret: false,
+			screen_length: DEFAULT_SCREEN_LENGTH,
+		}
+	}
+
+	fn state(&self) -> MutexGuard<'_, CiscoState> {
+		self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	/// The error IOS prints for a word it doesn't know, with a marker below it.
+	fn invalid(&self, line: &str, word: usize) -> Response {
+		let offset = line.split_whitespace()
+			.nth(word)
+			.and_then(|found| line.find(found))
+			.unwrap_or(0);
+		Response::output(format!("{}^\r\n% Invalid input detected at '^' marker.\r\n", " ".repeat(self.prompt().len() + offset)))
+	}
+
+	/// Resolves the keyword at the given word of the line.
+	fn keyword(&self, line: &str, word: usize, keywords: &[&'static str]) -> Result<&'static str, Response> {
+		let Some(text) = line.split_whitespace().nth(word) else {
+			return Err(Response::output("% Incomplete command.\r\n"));
+		};
+
+		match match_keyword(text, keywords) {
+			Keyword::Found(keyword) => Ok(keyword),
+			Keyword::Ambiguous => Err(Response::output(format!("% Ambiguous command:  \"{}\"\r\n", line.trim()))),
+			Keyword::Unknown => Err(self.invalid(line, word)),
+		}
+	}
+
+	fn exec(&mut self, line: &str) -> Result<Response, Response> {
+		let privileged = self.mode != Mode::UserExec;
+		let commands: &[&'static str] = if privileged {
+			&["archive", "configure", "disable", "enable", "exit", "logout", "quit", "show", "terminal", "write"]
+		} else {
+			&["enable", "exit", "logout", "quit", "show", "terminal"]
+		};
+
+		match self.keyword(line, 0, commands)? {
+			"enable" => {
+				if privileged || self.state().enable_secret.is_none() {
+					self.mode = Mode::PrivilegedExec;
+				} else {
+					self.awaiting_secret = true;
+				}
+				Ok(Response::output(""))
+			},
+			"disable" => {
+				self.mode = Mode::UserExec;
+				Ok(Response::output(""))
+			},
+			"exit" | "logout" | "quit" => Ok(Response::close("")),
+			"archive" => {
+				self.keyword(line, 1, &["config"])?;
+				match self.state().archive() {
+					Ok(_) => Ok(Response::output("")),
+					Err(error) => Ok(Response::output(error)),
+				}
+			},
+			"configure" => self.configure(line),
+			"terminal" => {
+				let setting = self.keyword(line, 1, &["length", "width"])?;
+				let value = line.split_whitespace()
+					.nth(2)
+					.ok_or_else(|| Response::output("% Incomplete command.\r\n"))?
+					.parse::<usize>()
+					.map_err(|_| self.invalid(line, 2))?;
+				if setting == "length" {
+					self.screen_length = value;
+				}
+				Ok(Response::output(""))
+			},
+			"write" => {
+				if line.split_whitespace().count() > 1 {
+					self.keyword(line, 1, &["memory"])?;
+				}
+				let mut state = self.state();
+				state.startup = state.running.clone();
+				Ok(Response::output("Building configuration...\r\n[OK]"))
+			},
+			_ => self.show(line, privileged),
+		}
+	}
+
+	/// The `configure` commands: entering the configuration mode, possibly with a revert timer,
+	/// and replacing the running configuration.
+	fn configure(&mut self, line: &str) -> Result<Response, Response> {
+		let words: Vec<&str> = line.split_whitespace().collect();
+		let minutes = |index: usize| -> Result<u64, Response> {
+			words.get(index)
+				.ok_or_else(|| Response::output("% Incomplete command.\r\n"))?
+				.parse::<u64>()
+				.ok()
+				.filter(|minutes| (1..=120).contains(minutes))
+				.ok_or_else(|| self.invalid(line, index))
+		};
+
+		match self.keyword(line, 1, &["confirm", "replace", "revert", "terminal"])? {
+			"terminal" => {
+				let mut output = String::new();
+				if words.len() > 2 {
+					self.keyword(line, 2, &["revert"])?;
+					self.keyword(line, 3, &["timer"])?;
+					let minutes = minutes(4)?;
+					let mut state = self.state();
+					let name = match state.archive() {
+						Ok(name) => name,
+						Err(error) => return Ok(Response::output(error)),
+					};
+					let running = state.running.clone();
+					state.revert = Some((Instant::now() + Duration::from_secs(minutes * 60), running));
+					output.push_str(&format!("Backing up current running config to {}\r\n\r\n", name));
+				}
+				self.mode = Mode::Config;
+				output.push_str("Enter configuration commands, one per line.  End with CNTL/Z.");
+				Ok(Response::output(output))
+			},
+			"replace" => {
+				let target = words.get(2).ok_or_else(|| Response::output("% Incomplete command.\r\n"))?;
+				let mut timer = None;
+				let mut index = 3;
+				while index < words.len() {
+					match self.keyword(line, index, &["force", "list", "time"])? {
+						"time" => {
+							timer = Some(minutes(index + 1)?);
+							index += 2;
+						},
+						_ => index += 1,
+					}
+				}
+
+				let mut state = self.state();
+				let Some(sections) = state.file(target) else {
+					return Ok(Response::output(format!("%Error opening {} (No such file or directory)", target)));
+				};
+				if let Some(minutes) = timer {
+					let running = state.running.clone();
+					state.revert = Some((Instant::now() + Duration::from_secs(minutes * 60), running));
+				}
+				let commands = state.replace(sections);
+				Ok(Response::output(format!(
+					"!Pass 1\r\n\r\n!List of Commands:\r\n{}\r\nend\r\n\r\nTotal number of passes: 1\r\nRollback Done",
+					commands.join("\r\n"),
+				)))
+			},
+			"confirm" => {
+				self.state().revert = None;
+				Ok(Response::output(""))
+			},
+			_ => {
+				self.keyword(line, 2, &["now", "timer"])?;
+				let mut state = self.state();
+				match state.revert.take() {
+					Some((_, sections)) => {
+						state.running = sections;
+						Ok(Response::output("Rollback Done"))
+					},
+					None => Ok(Response::output("%Error: No revert timer is running")),
+				}
+			},
+		}
+	}
+
+	fn show(&self, line: &str, privileged: bool) -> Result<Response, Response> {
+		let topics: &[&'static str] = if privileged {
+			&["archive", "clock", "ip", "running-config", "startup-config", "version"]
+		} else {
+			&["clock", "ip", "version"]
+		};
+
+		let topic = self.keyword(line, 1, topics)?;
+		if topic == "ip" {
+			self.keyword(line, 2, &["interface"])?;
+			self.keyword(line, 3, &["brief"])?;
+		}
+
+		// Resolve all keywords first, printing an error needs the lock for the prompt.
+		let state = self.state();
+		let output = match topic {
+			"archive" => {
+				let maximum = state.archive_setting("maximum")
+					.and_then(|maximum| maximum.parse().ok())
+					.unwrap_or(DEFAULT_ARCHIVE_MAXIMUM);
+				let mut output = format!(
+					"The maximum archive configurations allowed is {}.\r\nThere are currently {} archive configurations saved.",
+					maximum,
+					state.archives.len(),
+				);
+				if let Some(path) = state.archive_setting("path") {
+					output.push_str(&format!("\r\nThe next archive file will be named {}-{}", path, state.archive_count + 1));
+				}
+				output.push_str("\r\n Archive #  Name");
+				for (index, (name, _)) in state.archives.iter().enumerate() {
+					let marker = if index + 1 == state.archives.len() { " <- Most Recent" } else { "" };
+					output.push_str(&format!("\r\n   {:<8} {}{}", index + 1, name, marker));
+				}
+				output
+			},
+			"clock" => "*12:00:00.000 UTC Mon Mar 1 1993".to_string(),
+			"version" => format!(
+				"Cisco IOS Software, C2960X Software (C2960X-UNIVERSALK9-M), Version 15.2(7)E8, RELEASE SOFTWARE (fc1)\r\n\
+				Technical Support: http://www.cisco.com/techsupport\r\n\
+				Copyright (c) 1986-2023 by Cisco Systems, Inc.\r\n\
+				\r\n\
+				ROM: Bootstrap program is C2960X boot loader\r\n\
+				\r\n\
+				{} uptime is 1 week, 2 days, 3 hours, 4 minutes\r\n\
+				System image file is \"flash:c2960x-universalk9-mz.152-7.E8.bin\"\r\n\
+				\r\n\
+				cisco WS-C2960X-24TS-L (APM86XXX) processor with 524288K bytes of memory.\r\n\
+				Processor board ID FOC1234X0AB",
+				state.hostname,
+			),
+			"ip" => {
+				let mut output = "Interface              IP-Address      OK? Method Status                Protocol".to_string();
+				for section in state.running.iter().filter(|section| section.header.starts_with("interface ")) {
+					let name = &section.header["interface ".len()..];
+					let shutdown = section.children.iter().any(|child| child == "shutdown");
+					let address = section.children.iter()
+						.find_map(|child| child.strip_prefix("ip address "))
+						.and_then(|address| address.split_whitespace().next())
+						.unwrap_or("unassigned");
+					let status = if shutdown { "administratively down" } else { "up" };
+					let protocol = if shutdown { "down" } else { "up" };
+					output.push_str(&format!("\r\n{:<22} {:<15} YES manual {:<21} {}", name, address, status, protocol));
+				}
+				output
+			},
+			"running-config" => {
+				let lines = state.render(&state.running);
+				let size: usize = lines.iter().map(|line| line.len() + 1).sum();
+				format!("Building configuration...\r\n\r\nCurrent configuration : {} bytes\r\n{}", size, lines.join("\r\n"))
+			},
+			_ => {
+				let lines = state.render(&state.startup);
+				let size: usize = lines.iter().map(|line| line.len() + 1).sum();
+				format!("Using {} out of 524288 bytes\r\n{}", size, lines.join("\r\n"))
+			},
+		};
+
+		Ok(Response::output(output))
+	}
+
+	fn config(&mut self, line: &str) -> Result<Response, Response> {
+		let words: Vec<&str> = line.split_whitespace().collect();
+
+		match self.keyword(line, 0, GLOBAL_COMMANDS)? {
+			"end" | "exit" => self.mode = Mode::PrivilegedExec,
+			"do" => return self.run_do(line),
+			"hostname" => {
+				let hostname = words.get(1).ok_or_else(|| Response::output("% Incomplete command.\r\n"))?;
+				self.state().hostname = hostname.to_string();
+			},
+			"interface" => {
+				let name = words[1..].join("");
+				if name.is_empty() {
+					return Err(Response::output("% Incomplete command.\r\n"));
+				}
+				let Some((name, creatable)) = expand_interface(&name) else {
+					return Err(self.invalid(line, 1));
+				};
+
+				let header = format!("interface {}", name);
+				let mut state = self.state();
+				if state.section_mut(&header).is_none() {
+					if !creatable {
+						drop(state);
+						return Err(self.invalid(line, 1));
+					}
+					state.running.push(Section::new(header.clone(), &[]));
+				}
+				drop(state);
+				self.mode = Mode::Sub(SubMode::Interface, header);
+			},
+			"vlan" => {
+				let id = words.get(1).ok_or_else(|| Response::output("% Incomplete command.\r\n"))?;
+				if !id.parse::<u16>().is_ok_and(|id| (1..=4094).contains(&id)) {
+					return Err(self.invalid(line, 1));
+				}
+				self.enter_section(SubMode::Vlan, format!("vlan {}", id));
+			},
+			"archive" => {
+				if words.len() > 1 {
+					return Err(self.invalid(line, 1));
+				}
+				self.enter_section(SubMode::Archive, "archive".to_string());
+			},
+			"router" => {
+				let protocol = self.keyword(line, 1, &["bgp", "eigrp", "ospf"])?;
+				let id = words.get(2).ok_or_else(|| Response::output("% Incomplete command.\r\n"))?;
+				self.enter_section(SubMode::Router, format!("router {} {}", protocol, id));
+			},
+			"line" => {
+				let kind = self.keyword(line, 1, &["console", "vty"])?;
+				if words.len() < 3 {
+					return Err(Response::output("% Incomplete command.\r\n"));
+				}
+				let kind = if kind == "console" { "con" } else { kind };
+				self.enter_section(SubMode::Line, format!("line {} {}", kind, words[2..].join(" ")));
+			},
+			"no" => {
+				let rest = words[1..].join(" ");
+				if rest.is_empty() {
+					return Err(Response::output("% Incomplete command.\r\n"));
+				}
+				self.state().running.retain(|section| section.header != rest);
+			},
+			_ => {
+				let command = words.join(" ");
+				let mut state = self.state();
+				if !state.running.iter().any(|section| section.header == command) {
+					state.running.push(Section::new(command, &[]));
+				}
+			},
+		}
+
+		Ok(Response::output(""))
+	}
+
+	fn enter_section(&mut self, mode: SubMode, header: String) {
+		let mut state = self.state();
+		if state.section_mut(&header).is_none() {
+			state.running.push(Section::new(header.clone(), &[]));
+		}
+		drop(state);
+		self.mode = Mode::Sub(mode, header);
+	}
+
+	fn sub_config(&mut self, line: &str, mode: SubMode, header: String) -> Result<Response, Response> {
+		let words: Vec<&str> = line.split_whitespace().collect();
+
+		// Global commands are also accepted in the sub-modes and leave them.
+		let keyword = match match_keyword(words[0], mode.commands()) {
+			Keyword::Found(keyword) => keyword,
+			Keyword::Unknown if matches!(match_keyword(words[0], GLOBAL_COMMANDS), Keyword::Found(_)) => {
+				self.mode = Mode::Config;
+				return self.config(line);
+			},
+			_ => self.keyword(line, 0, mode.commands())?,
+		};
+
+		let mut state = self.state();
+		let Some(section) = state.section_mut(&header) else {
+			drop(state);
+			self.mode = Mode::Config;
+			return Ok(Response::output(""));
+		};
+
+		match keyword {
+			"exit" => {
+				drop(state);
+				self.mode = Mode::Config;
+			},
+			"end" => {
+				drop(state);
+				self.mode = Mode::PrivilegedExec;
+			},
+			"do" => {
+				drop(state);
+				return self.run_do(line);
+			},
+			"no" => {
+				if words.len() < 2 {
+					drop(state);
+					return Err(Response::output("% Incomplete command.\r\n"));
+				}
+				let rest = words[1..].join(" ");
+				let single_valued = SINGLE_VALUED.contains(&words[1]);
+				section.children.retain(|child| {
+					child != &rest && !(single_valued && child.split_whitespace().next() == Some(words[1]))
+				});
+				if rest == "ip address" {
+					section.children.push("no ip address".to_string());
+				}
+			},
+			_ => {
+				let mut command = words.clone();
+				command[0] = keyword;
+				let command = command.join(" ");
+
+				let key: Vec<&str> = if SINGLE_VALUED.contains(&keyword) {
+					vec![keyword]
+				} else {
+					command.split_whitespace().take(2).collect()
+				};
+				section.children.retain(|child| {
+					let child_words: Vec<&str> = child.split_whitespace().take(key.len()).collect();
+					child_words != key && !(command.starts_with("ip address") && child == "no ip address")
+				});
+				section.children.push(command);
+			},
+		}
+
+		Ok(Response::output(""))
+	}
+
+	/// Runs an exec command from the configuration mode, like `do show running-config`.
+	fn run_do(&mut self, line: &str) -> Result<Response, Response> {
+		let command = line.trim_start()
+			.split_once(char::is_whitespace)
+			.map(|(_, command)| command.trim())
+			.unwrap_or_default();
+		if command.is_empty() {
+			return Err(Response::output("% Incomplete command.\r\n"));
+		}
+
+		let mode = std::mem::replace(&mut self.mode, Mode::PrivilegedExec);
+		let response = match self.keyword(command, 0, &["show", "write"]) {
+			Ok(_) => self.exec(command),
+			Err(response) => Err(response),
+		};
+		self.mode = mode;
+		response
+	}
+}
+
+impl Cli for CiscoCli {
+	fn screen_length(&self) -> usize {
+		self.screen_length
+	}
+
+	fn prompt(&self) -> String {
+		if self.awaiting_secret {
+			return "Password: ".to_string();
+		}
+
+		let hostname = self.state().hostname.clone();
+		match &self.mode {
+			Mode::UserExec => format!("{}>", hostname),
+			Mode::PrivilegedExec => format!("{}#", hostname),
+			Mode::Config => format!("{}(config)#", hostname),
+			Mode::Sub(mode, _) => format!("{}({})#", hostname, mode.prompt()),
+		}
+	}
+
+	fn hides_input(&self) -> bool {
+		self.awaiting_secret
+	}
+
+	fn execute(&mut self, line: &str) -> Response {
+		if self.awaiting_secret {
+			self.awaiting_secret = false;
+			if self.state().enable_secret.as_deref() == Some(line) {
+				self.mode = Mode::PrivilegedExec;
+				return Response::output("");
+			}
+			return Response::output("% Bad secrets\r\n");
+		}
+
+		if line.trim().is_empty() {
+			return Response::output("");
+		}
+
+		self.state().check_revert_timer();
+		let result = match self.mode.clone() {
+			Mode::UserExec | Mode::PrivilegedExec => self.exec(line),
+			Mode::Config => self.config(line),
+			Mode::Sub(mode, header) => self.sub_config(line, mode, header),
+		};
+		result.unwrap_or_else(|error| error)
+	}
+}
+
+/// Expands an abbreviated interface name like `gi0/1` to `GigabitEthernet0/1`. Also returns
+/// whether interfaces of this type can be created.
+fn expand_interface(name: &str) -> Option<(String, bool)> {
+	let index = name.find(|c: char| c.is_ascii_digit())?;
+	let (kind, number) = name.split_at(index);
+	if kind.is_empty() || !number.chars().all(|c| c.is_ascii_digit() || c == '/' || c == '.') {
+		return None;
+	}
+
+	let kind = kind.to_lowercase();
+	let mut matches = INTERFACE_TYPES.iter().filter(|(full, _)| full.to_lowercase().starts_with(&kind));
+	match (matches.next(), matches.next()) {
+		(Some((full, creatable)), None) => Some((format!("{}{}", full, number), *creatable)),
+		_ => None,
+	}
+}
@@ -0,0 +1,926 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::simulator::{match_keyword, Cli, Keyword, Response, DEFAULT_SCREEN_LENGTH};
+
+/// The statements allowed at the top of the configuration hierarchy.
+const TOP_LEVEL_STATEMENTS: &[&str] = &[
+	"access", "applications", "chassis", "class-of-service", "firewall", "forwarding-options", "interfaces",
+	"policy-options", "protocols", "routing-instances", "routing-options", "security", "services", "snmp", "system",
+	"vlans",
+];
+
+const OPERATIONAL_COMMANDS: &[&str] = &["configure", "exit", "quit", "set", "show"];
+const CONFIGURATION_COMMANDS: &[&str] = &["commit", "delete", "edit", "exit", "load", "quit", "rollback", "run", "set", "show", "top", "up"];
+/// The commands allowed in the input of `load set terminal`.
+const LOAD_SET_COMMANDS: &[&str] = &["delete", "edit", "set", "top", "up"];
+
+/// The statements that only take a single value, which `set` replaces instead of adding another.
+const SINGLE_VALUE_STATEMENTS: &[&str] = &["description", "host-name", "mtu", "vlan-id"];
+
+/// The statements that are rendered together with the name that follows them, like `unit 0`.
+const NAMED_STATEMENTS: &[&str] = &["area", "family", "group", "interface", "neighbor", "policy-statement", "security-zone", "term", "unit"];
+
+/// The physical interfaces of the simulated device.
+const INTERFACES: &[&str] = &["ge-0/0/0", "ge-0/0/1", "ge-0/0/2", "ge-0/0/3", "ge-0/0/4", "ge-0/0/5", "ge-0/0/6", "ge-0/0/7"];
+
+/// The configuration of a simulated Junos device, as statements in the `set` format without the
+/// leading `set`.
+pub(crate) struct JunosState {
+	hostname: String,
+	candidate: Vec<String>,
+	/// The committed configurations, the active one first, like the rollback numbers.
+	history: Vec<Vec<String>>,
+}
+
+impl JunosState {
+	pub(crate) fn new(hostname: &str) -> JunosState {
+		let committed: Vec<String> = [
+			format!("system host-name {}", hostname),
+			"system services ssh".to_string(),
+			"system services telnet".to_string(),
+			"interfaces ge-0/0/0 unit 0 family inet address 192.168.1.1/24".to_string(),
+			"interfaces ge-0/0/1 unit 0 family ethernet-switching".to_string(),
+			"vlans default vlan-id 1".to_string(),
+		].into();
+
+		JunosState {
+			hostname: hostname.to_string(),
+			candidate: committed.clone(),
+			history: vec![committed],
+		}
+	}
+
+	fn committed(&self) -> &[String] {
+		&self.history[0]
+	}
+
+	/// Adds a statement to the candidate configuration, replacing the old value of statements
+	/// that only take one.
+	fn set(&mut self, statement: String) {
+		let words: Vec<&str> = statement.split_whitespace().collect();
+		if let [path @ .., keyword, _] = words.as_slice() {
+			if SINGLE_VALUE_STATEMENTS.contains(keyword) {
+				let prefix = format!("{} {} ", path.join(" "), keyword);
+				let prefix = prefix.trim_start();
+				self.candidate.retain(|existing| !existing.starts_with(prefix));
+			}
+		}
+		if !self.candidate.contains(&statement) {
+			self.candidate.push(statement);
+		}
+	}
+}
+
+/// How `load` combines the loaded statements with the candidate configuration.
+#[derive(Clone, Copy, PartialEq)]
+enum LoadAction {
+	Merge,
+	Override,
+	Replace,
+	Set,
+}
+
+enum Mode {
+	Operational,
+	Configuration(Vec<String>),
+	/// Asking whether to leave the configuration mode with uncommitted changes.
+	ConfirmExit(Vec<String>),
+	/// Reading the input of `load ... terminal` until Ctrl-D.
+	Load {
+		action: LoadAction,
+		path: Vec<String>,
+		lines: Vec<String>,
+	},
+}
+
+/// The CLI of a simulated Junos device.
+pub(crate) struct JunosCli {
+	state: Arc<Mutex<JunosState>>,
+	username: String,
+	mode: Mode,
+	/// The levels `edit` came from, which `exit` goes back to.
+	edit_stack: Vec<Vec<String>>,
+	screen_length: usize,
+}
+
+impl JunosCli {
+	pub(crate) fn new(state: Arc<Mutex<JunosState>>, username: &str) -> JunosCli {
+		JunosCli {
+			state,
+			username: username.to_string(),
+			mode: Mode::Operational,
+			edit_stack: Vec::new(),
+			screen_length: DEFAULT_SCREEN_LENGTH,
+		}
+	}
+
+	fn state(&self) -> MutexGuard<'_, JunosState> {
+		self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	/// The length of the prompt line, to place the error marker below the input.
+	fn prompt_length(&self) -> usize {
+		self.prompt().lines().last().map_or(0, str::len)
+	}
+
+	/// The error Junos prints for a word it doesn't know, with a marker below it.
+	fn error(&self, line: &str, word: usize, message: &str) -> Response {
+		let offset = line.split_whitespace()
+			.nth(word)
+			.and_then(|found| line.find(found))
+			.unwrap_or(line.len());
+		Response::output(format!("{}^\r\n{}\r\n", " ".repeat(self.prompt_length() + offset), message))
+	}
+
+	fn keyword(&self, line: &str, word: usize, keywords: &[&'static str]) -> Result<&'static str, Response> {
+		let Some(text) = line.split_whitespace().nth(word) else {
+			return Err(self.error(line, word, "syntax error, expecting <command>."));
+		};
+
+		match match_keyword(text, keywords) {
+			Keyword::Found(keyword) => Ok(keyword),
+			Keyword::Ambiguous => Err(self.error(line, word, &format!("'{}' is ambiguous.", text))),
+			Keyword::Unknown if word == 0 => Err(self.error(line, word, "unknown command.")),
+			Keyword::Unknown => Err(self.error(line, word, "syntax error, expecting <command>.")),
+		}
+	}
+
+	fn operational(&mut self, line: &str) -> Result<Response, Response> {
+		match self.keyword(line, 0, OPERATIONAL_COMMANDS)? {
+			"configure" => {
+				self.edit_stack.clear();
+				self.mode = Mode::Configuration(Vec::new());
+				Ok(Response::output("Entering configuration mode"))
+			},
+			"exit" | "quit" => Ok(Response::close("")),
+			"set" => {
+				self.keyword(line, 1, &["cli"])?;
+				let setting = self.keyword(line, 2, &["screen-length", "screen-width"])?;
+				let value = line.split_whitespace()
+					.nth(3)
+					.and_then(|value| value.parse::<usize>().ok())
+					.ok_or_else(|| self.error(line, 3, "syntax error, expecting <number>."))?;
+				match setting {
+					"screen-length" => {
+						self.screen_length = value;
+						Ok(Response::output(format!("Screen length set to {}", value)))
+					},
+					_ => Ok(Response::output(format!("Screen width set to {}", value))),
+				}
+			},
+			_ => self.show_operational(line),
+		}
+	}
+
+	fn show_operational(&self, line: &str) -> Result<Response, Response> {
+		let (command, pipes) = split_pipes(line);
+		let topic = self.keyword(command, 1, &["configuration", "interfaces", "version"])?;
+		if topic == "interfaces" && command.split_whitespace().count() > 2 {
+			self.keyword(command, 2, &["terse"])?;
+		}
+
+		let state = self.state();
+		let output = match topic {
+			"version" => format!("Hostname: {}\r\nModel: srx300\r\nJunos: 21.4R3-S5.4\r\nJUNOS Software Release [21.4R3-S5.4]", state.hostname),
+			"interfaces" => {
+				let mut output = "Interface               Admin Link Proto    Local                 Remote".to_string();
+				for interface in INTERFACES {
+					let disabled = state.committed().iter().any(|statement| *statement == format!("interfaces {} disable", interface));
+					let admin = if disabled { "down" } else { "up" };
+					output.push_str(&format!("\r\n{:<23} {:<5} {}", interface, admin, admin));
+				}
+				output
+			},
+			_ => {
+				let path: Vec<&str> = command.split_whitespace().skip(2).collect();
+				let output = render(state.committed(), &path, &pipes);
+				match path.is_empty() && pipes.is_empty() {
+					true => format!("## Last commit: 2024-05-01 12:00:00 UTC by {}\r\n{}", self.username, output),
+					false => output,
+				}
+			},
+		};
+
+		Ok(Response::output(output))
+	}
+
+	fn configuration(&mut self, line: &str, mut path: Vec<String>) -> Result<Response, Response> {
+		let words: Vec<&str> = line.split_whitespace().collect();
+		let keyword = self.keyword(line, 0, CONFIGURATION_COMMANDS)?;
+
+		let mut statement: Vec<String> = path.clone();
+		statement.extend(words.iter().skip(1).map(|word| word.to_string()));
+
+		match keyword {
+			"set" | "edit" => {
+				let Some(first) = statement.first() else {
+					return Err(self.error(line, 1, "syntax error, expecting <statement> or <identifier>."));
+				};
+				if !TOP_LEVEL_STATEMENTS.contains(&first.as_str()) {
+					return Err(self.error(line, 1, "syntax error."));
+				}
+
+				if keyword == "edit" {
+					self.edit_stack.push(path);
+					self.mode = Mode::Configuration(statement);
+				} else {
+					self.state().set(statement.join(" "));
+				}
+				Ok(Response::output(""))
+			},
+			"delete" => {
+				let statement = statement.join(" ");
+				let prefix = format!("{} ", statement);
+				let mut state = self.state();
+				let count = state.candidate.len();
+				state.candidate.retain(|existing| !statement.is_empty() && *existing != statement && !existing.starts_with(&prefix));
+				if state.candidate.len() == count {
+					return Ok(Response::output("warning: statement not found"));
+				}
+				Ok(Response::output(""))
+			},
+			"up" => {
+				path.pop();
+				self.edit_stack.clear();
+				self.mode = Mode::Configuration(path);
+				Ok(Response::output(""))
+			},
+			"top" => {
+				self.edit_stack.clear();
+				self.mode = Mode::Configuration(Vec::new());
+				Ok(Response::output(""))
+			},
+			"exit" | "quit" => {
+				if words.len() > 1 {
+					self.keyword(line, 1, &["configuration-mode"])?;
+				} else if !path.is_empty() {
+					self.mode = Mode::Configuration(self.edit_stack.pop().unwrap_or_default());
+					return Ok(Response::output(""));
+				}
+
+				let state = self.state();
+				if state.candidate != state.committed() {
+					drop(state);
+					self.mode = Mode::ConfirmExit(path);
+					return Ok(Response::output("The configuration has been changed but not committed"));
+				}
+				drop(state);
+				self.mode = Mode::Operational;
+				Ok(Response::output("Exiting configuration mode"))
+			},
+			"commit" => self.commit(line),
+			"load" => {
+				let action = match self.keyword(line, 1, &["merge", "override", "replace", "set"])? {
+					"merge" => LoadAction::Merge,
+					"override" => LoadAction::Override,
+					"replace" => LoadAction::Replace,
+					_ => LoadAction::Set,
+				};
+				self.keyword(line, 2, &["terminal"])?;
+				self.mode = Mode::Load {
+					action,
+					path,
+					lines: Vec::new(),
+				};
+				Ok(Response::output("[Type ^D at a new line to end input]"))
+			},
+			"rollback" => {
+				let number = match words.get(1) {
+					Some(number) => number.parse::<usize>().map_err(|_| self.error(line, 1, "syntax error, expecting <number>."))?,
+					None => 0,
+				};
+
+				let mut state = self.state();
+				let Some(configuration) = state.history.get(number).cloned() else {
+					return Ok(Response::output(format!("error: Cannot find rollback file {}", number)));
+				};
+				state.candidate = configuration;
+				Ok(Response::output("load complete"))
+			},
+			"run" => {
+				let command = line.trim_start()
+					.split_once(char::is_whitespace)
+					.map(|(_, command)| command.trim())
+					.unwrap_or_default();
+				self.keyword(command, 0, &["show"])?;
+				self.show_operational(command)
+			},
+			_ => {
+				let (command, pipes) = split_pipes(line);
+				if pipes.iter().any(|pipe| pipe == "compare") {
+					let state = self.state();
+					return Ok(Response::output(compare(state.committed(), &state.candidate)));
+				}
+				let mut show_path: Vec<String> = path.clone();
+				show_path.extend(command.split_whitespace().skip(1).map(String::from));
+				let show_path: Vec<&str> = show_path.iter().map(String::as_str).collect();
+				Ok(Response::output(render(&self.state().candidate, &show_path, &pipes)))
+			},
+		}
+	}
+
+	/// Applies the input of `load ... terminal` and returns the output Junos shows at the end.
+	fn load(&mut self, action: LoadAction, path: Vec<String>, lines: &[String]) -> String {
+		self.mode = Mode::Configuration(path.clone());
+		if action == LoadAction::Set {
+			return self.load_set(path, lines);
+		}
+
+		let loaded = match parse_curly(lines) {
+			Ok(loaded) => loaded,
+			Err(error) => return format!("{}\r\nload complete (1 errors)", error),
+		};
+
+		let mut state = self.state();
+		match action {
+			LoadAction::Override => state.candidate.clear(),
+			LoadAction::Replace => {
+				for prefix in &loaded.replaced {
+					let below = format!("{} ", prefix);
+					state.candidate.retain(|existing| existing != prefix && !existing.starts_with(&below));
+				}
+			},
+			_ => {},
+		}
+		for statement in loaded.statements {
+			state.set(statement);
+		}
+		"load complete".to_string()
+	}
+
+	/// Runs every line of the input of `load set terminal` as a configuration command.
+	fn load_set(&mut self, path: Vec<String>, lines: &[String]) -> String {
+		let edit_stack = self.edit_stack.clone();
+		let mut output = Vec::new();
+		let mut errors = 0;
+
+		// The statements are relative to the top of the hierarchy, wherever the load started.
+		self.mode = Mode::Configuration(Vec::new());
+
+		for (number, line) in lines.iter().enumerate() {
+			if line.trim().is_empty() || line.trim_start().starts_with('#') {
+				continue;
+			}
+			let result = self.keyword(line, 0, LOAD_SET_COMMANDS)
+				.and_then(|_| {
+					let path = match &self.mode {
+						Mode::Configuration(path) => path.clone(),
+						_ => Vec::new(),
+					};
+					self.configuration(line, path)
+				});
+			if let Err(error) = result {
+				errors += 1;
+				let message = error.output.lines().map(str::trim).rfind(|line| !line.is_empty()).unwrap_or_default().to_string();
+				output.push(format!("terminal:{}:(0) {}", number + 1, message));
+			}
+		}
+
+		self.edit_stack = edit_stack;
+		self.mode = Mode::Configuration(path);
+		match errors {
+			0 => output.push("load complete".to_string()),
+			errors => output.push(format!("load complete ({} errors)", errors)),
+		}
+		output.join("\r\n")
+	}
+
+	fn commit(&mut self, line: &str) -> Result<Response, Response> {
+		let option = match line.split_whitespace().nth(1) {
+			Some(_) => Some(self.keyword(line, 1, &["and-quit", "check", "comment", "confirmed", "synchronize"])?),
+			None => None,
+		};
+
+		let mut state = self.state();
+		if let Some(error) = check(&state.candidate) {
+			return Ok(Response::output(error));
+		}
+		if option == Some("check") {
+			return Ok(Response::output("configuration check succeeds"));
+		}
+
+		let candidate = state.candidate.clone();
+		if let Some(hostname) = candidate.iter().find_map(|statement| statement.strip_prefix("system host-name ")) {
+			state.hostname = hostname.to_string();
+		}
+		state.history.insert(0, candidate);
+		drop(state);
+
+		match option {
+			Some("and-quit") => {
+				self.mode = Mode::Operational;
+				Ok(Response::output("commit complete\r\nExiting configuration mode"))
+			},
+			Some("confirmed") => {
+				let minutes = line.split_whitespace().nth(2).unwrap_or("10");
+				Ok(Response::output(format!("commit confirmed will be automatically rolled back in {} minutes unless confirmed\r\ncommit complete", minutes)))
+			},
+			Some("synchronize") => Ok(Response::output("re0:\r\nconfiguration check succeeds\r\ncommit complete")),
+			_ => Ok(Response::output("commit complete")),
+		}
+	}
+}
+
+impl Cli for JunosCli {
+	fn screen_length(&self) -> usize {
+		self.screen_length
+	}
+
+	fn prompt(&self) -> String {
+		let hostname = self.state().hostname.clone();
+		match &self.mode {
+			Mode::Operational => format!("{}@{}> ", self.username, hostname),
+			Mode::Configuration(path) if path.is_empty() => format!("\r\n[edit]\r\n{}@{}# ", self.username, hostname),
+			Mode::Configuration(path) => format!("\r\n[edit {}]\r\n{}@{}# ", path.join(" "), self.username, hostname),
+			Mode::ConfirmExit(_) => "Exit with uncommitted changes? [yes,no] (yes) ".to_string(),
+			Mode::Load { .. } => String::new(),
+		}
+	}
+
+	fn execute(&mut self, line: &str) -> Response {
+		if let Mode::Load { lines, .. } = &mut self.mode {
+			lines.push(line.to_string());
+			return Response::output("");
+		}
+
+		let mode = std::mem::replace(&mut self.mode, Mode::Operational);
+		if let Mode::ConfirmExit(path) = mode {
+			if matches!(line.trim(), "" | "y" | "yes") {
+				return Response::output("Exiting configuration mode");
+			}
+			self.mode = Mode::Configuration(path);
+			return Response::output("");
+		}
+		self.mode = mode;
+
+		if line.trim().is_empty() {
+			return Response::output("");
+		}
+
+		let result = match &self.mode {
+			Mode::Configuration(path) => {
+				let path = path.clone();
+				self.configuration(line, path)
+			},
+			_ => self.operational(line),
+		};
+		result.unwrap_or_else(|error| error)
+	}
+
+	fn end_of_input(&mut self) -> Option<Response> {
+		let Mode::Load { action, path, lines } = std::mem::replace(&mut self.mode, Mode::Operational) else {
+			return None;
+		};
+		Some(Response::output(self.load(action, path, &lines)))
+	}
+}
+
+/// Splits the pipes like `| display set` off a command.
+fn split_pipes(line: &str) -> (&str, Vec<String>) {
+	let mut parts = line.split('|');
+	let command = parts.next().unwrap_or_default();
+	(command, parts.map(|pipe| pipe.trim().to_string()).collect())
+}
+
+/// Renders the configuration below a path, in the curly brace format or with `| display set`,
+/// `| display xml` or `| display json` in the other formats.
+fn render(statements: &[String], path: &[&str], pipes: &[String]) -> String {
+	let prefix = path.join(" ");
+	let below: Vec<&str> = statements.iter()
+		.filter_map(|statement| match prefix.is_empty() {
+			true => Some(statement.as_str()),
+			false => statement.strip_prefix(&prefix).and_then(|rest| rest.strip_prefix(' ')),
+		})
+		.collect();
+
+	if pipes.iter().any(|pipe| pipe == "display set") {
+		return below.iter()
+			.map(|statement| match prefix.is_empty() {
+				true => format!("set {}", statement),
+				false => format!("set {} {}", prefix, statement),
+			})
+			.collect::<Vec<String>>()
+			.join("\r\n");
+	}
+
+	let mut root = Node::default();
+	for statement in below {
+		root.insert(statement.split_whitespace());
+	}
+	let mut lines = Vec::new();
+	if pipes.iter().any(|pipe| pipe == "display xml" || pipe == "display json") {
+		root = root.structured();
+	}
+	if pipes.iter().any(|pipe| pipe == "display xml") {
+		lines.push("<rpc-reply xmlns:junos=\"http://xml.juniper.net/junos/21.4R3/junos\">".to_string());
+		lines.push("    <configuration junos:commit-seconds=\"1714564800\" junos:commit-user=\"admin\">".to_string());
+		for child in &root.children {
+			child.render_xml(&root, 2, &mut lines);
+		}
+		lines.extend(["    </configuration>", "    <cli>", "        <banner></banner>", "    </cli>", "</rpc-reply>"].map(String::from));
+		return lines.join("\r\n");
+	}
+	if pipes.iter().any(|pipe| pipe == "display json") {
+		lines.push("{".to_string());
+		lines.push("    \"configuration\" : {".to_string());
+		root.render_json_members(2, &mut lines);
+		lines.push("    }".to_string());
+		lines.push("}".to_string());
+		return lines.join("\r\n");
+	}
+	for child in &root.children {
+		child.render(0, &mut lines);
+	}
+	lines.join("\r\n")
+}
+
+/// Renders the difference between the active and the candidate configuration like
+/// `show | compare`. Every change is shown below the deepest hierarchy level that both have.
+fn compare(active: &[String], candidate: &[String]) -> String {
+	let removed = active.iter().filter(|statement| !candidate.contains(statement)).map(|statement| ('-', statement, candidate));
+	let added = candidate.iter().filter(|statement| !active.contains(statement)).map(|statement| ('+', statement, active));
+
+	// The changes below every section, removed and added ones apart.
+	let mut sections: Vec<(String, Node, Node)> = Vec::new();
+	for (sign, statement, other) in removed.chain(added) {
+		let path = levels(statement);
+		let other: Vec<Vec<String>> = other.iter().map(|statement| levels(statement)).collect();
+		let depth = (0..path.len().saturating_sub(1))
+			.rev()
+			.find(|&depth| other.iter().any(|other| other.starts_with(&path[..depth])))
+			.unwrap_or_default();
+
+		let section = path[..depth].join(" ");
+		let index = match sections.iter().position(|(existing, _, _)| *existing == section) {
+			Some(index) => index,
+			None => {
+				sections.push((section, Node::default(), Node::default()));
+				sections.len() - 1
+			},
+		};
+		let rest = path[depth..].join(" ");
+		let (_, removed, added) = &mut sections[index];
+		match sign {
+			'-' => removed.insert(rest.split_whitespace()),
+			_ => added.insert(rest.split_whitespace()),
+		}
+	}
+
+	let mut lines = Vec::new();
+	for (section, removed, added) in sections {
+		match section.is_empty() {
+			true => lines.push("[edit]".to_string()),
+			false => lines.push(format!("[edit {}]", section)),
+		}
+		for (sign, node) in [('-', removed), ('+', added)] {
+			let mut rendered = Vec::new();
+			for child in &node.children {
+				child.render(1, &mut rendered);
+			}
+			lines.extend(rendered.into_iter().map(|line| format!("{}{}", sign, &line[1..])));
+		}
+	}
+	lines.join("\r\n")
+}
+
+/// Splits a statement into the hierarchy levels it's rendered with, where named statements like
+/// `unit 0` are one level.
+fn levels(statement: &str) -> Vec<String> {
+	let mut words = statement.split_whitespace();
+	let mut levels = Vec::new();
+	while let Some(word) = words.next() {
+		match NAMED_STATEMENTS.contains(&word) {
+			true => levels.push(format!("{} {}", word, words.next().unwrap_or_default()).trim_end().to_string()),
+			false => levels.push(word.to_string()),
+		}
+	}
+	levels
+}
+
+/// A node of the configuration hierarchy.
+#[derive(Default)]
+struct Node {
+	name: String,
+	children: Vec<Node>,
+}
+
+impl Node {
+	fn insert<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) {
+		let Some(mut word) = words.next().map(String::from) else {
+			return;
+		};
+		if NAMED_STATEMENTS.contains(&word.as_str()) {
+			if let Some(name) = words.next() {
+				word = format!("{} {}", word, name);
+			}
+		}
+
+		let index = match self.children.iter().position(|child| child.name == word) {
+			Some(index) => index,
+			None => {
+				self.children.push(Node { name: word, children: Vec::new() });
+				self.children.len() - 1
+			},
+		};
+		self.children[index].insert(words);
+	}
+
+	/// Whether the node names something like an interface, whose statements are always shown in
+	/// a block.
+	fn is_identifier(&self) -> bool {
+		self.name.contains(|c: char| c.is_ascii_digit())
+	}
+
+	/// Rebuilds the hierarchy like Junos structures it in XML and JSON, where `family inet` is an
+	/// `inet` container inside a `family` container instead of a named statement.
+	fn structured(self) -> Node {
+		let mut node = Node { name: self.name, children: Vec::new() };
+		for child in self.children {
+			let child = child.structured();
+			let Some(protocol) = child.name.strip_prefix("family ") else {
+				node.children.push(child);
+				continue;
+			};
+
+			let protocol = Node { name: protocol.to_string(), children: child.children };
+			match node.children.iter_mut().find(|existing| existing.name == "family") {
+				Some(family) => family.children.push(protocol),
+				None => node.children.push(Node { name: "family".to_string(), children: vec![protocol] }),
+			}
+		}
+		node
+	}
+
+	/// Whether this is a statement with multiple values like the addresses of `address`, which are
+	/// separate entries with the same element in XML and JSON.
+	fn is_list(&self) -> bool {
+		!self.name.contains(' ')
+			&& !SINGLE_VALUE_STATEMENTS.contains(&self.name.as_str())
+			&& !self.children.is_empty()
+			&& self.children.iter().all(|child| child.children.is_empty() && child.is_identifier())
+	}
+
+	/// The element for this node in XML and JSON, and its name if it's an entry of a list like
+	/// `unit 0` or an interface below `interfaces`.
+	fn element(&self, parent: &Node) -> (String, Option<String>) {
+		if let Some((element, name)) = self.name.split_once(' ') {
+			return (element.to_string(), Some(name.to_string()));
+		}
+		match parent.name.strip_suffix('s') {
+			Some(element) if self.is_identifier() => (element.to_string(), Some(self.name.clone())),
+			_ => (self.name.clone(), None),
+		}
+	}
+
+	/// The value of a statement that only has one, which is rendered on the same line.
+	fn value(&self) -> Option<&str> {
+		if self.is_list() {
+			return None;
+		}
+		match self.children.as_slice() {
+			[value] if value.children.is_empty() && !self.is_identifier() => Some(&value.name),
+			_ => None,
+		}
+	}
+
+	fn render_xml(&self, parent: &Node, depth: usize, lines: &mut Vec<String>) {
+		let indent = "    ".repeat(depth);
+		let (element, name) = self.element(parent);
+		if self.is_list() {
+			for entry in &self.children {
+				lines.push(format!("{}<{}>", indent, element));
+				lines.push(format!("{}    <name>{}</name>", indent, entry.name));
+				lines.push(format!("{}</{}>", indent, element));
+			}
+			return;
+		}
+		match (&name, self.value()) {
+			(None, _) if self.children.is_empty() => lines.push(format!("{}<{}/>", indent, element)),
+			(None, Some(value)) => lines.push(format!("{}<{}>{}</{}>", indent, element, value, element)),
+			_ => {
+				lines.push(format!("{}<{}>", indent, element));
+				if let Some(name) = &name {
+					lines.push(format!("{}    <name>{}</name>", indent, name));
+				}
+				for child in &self.children {
+					child.render_xml(self, depth + 1, lines);
+				}
+				lines.push(format!("{}</{}>", indent, element));
+			},
+		}
+	}
+
+	/// Renders the children of this node as the members of a JSON object. Entries of lists are
+	/// grouped in an array.
+	fn render_json_members(&self, depth: usize, lines: &mut Vec<String>) {
+		let indent = "    ".repeat(depth);
+		type Entry<'a> = (&'a Node, Option<String>);
+		let mut groups: Vec<(String, Vec<Entry>)> = Vec::new();
+		for child in &self.children {
+			let (element, name) = child.element(self);
+			match groups.iter_mut().find(|(existing, _)| *existing == element) {
+				Some((_, entries)) => entries.push((child, name)),
+				None => groups.push((element, vec![(child, name)])),
+			}
+		}
+
+		let count = groups.len();
+		for (index, (element, entries)) in groups.into_iter().enumerate() {
+			let comma = if index + 1 < count { "," } else { "" };
+			match entries.as_slice() {
+				[(node, None)] if node.is_list() => {
+					let names: Vec<String> = node.children.iter()
+						.map(|entry| format!("{}    {{\r\n{}        \"name\" : \"{}\"\r\n{}    }}", indent, indent, entry.name, indent))
+						.collect();
+					lines.push(format!("{}\"{}\" : [", indent, element));
+					lines.push(names.join(",\r\n"));
+					lines.push(format!("{}]{}", indent, comma));
+				},
+				[(node, None)] if node.children.is_empty() => lines.push(format!("{}\"{}\" : [null]{}", indent, element, comma)),
+				[(node, None)] if node.value().is_some() => {
+					lines.push(format!("{}\"{}\" : \"{}\"{}", indent, element, node.value().unwrap_or_default(), comma));
+				},
+				[(node, None)] => {
+					lines.push(format!("{}\"{}\" : {{", indent, element));
+					node.render_json_members(depth + 1, lines);
+					lines.push(format!("{}}}{}", indent, comma));
+				},
+				entries => {
+					lines.push(format!("{}\"{}\" : [", indent, element));
+					for (entry, (node, name)) in entries.iter().enumerate() {
+						lines.push(format!("{}    {{", indent));
+						let mut members = vec![format!("{}        \"name\" : \"{}\"", indent, name.as_deref().unwrap_or(&node.name))];
+						node.render_json_members(depth + 2, &mut members);
+						let last = members.len() - 1;
+						for (line_index, member) in members.into_iter().enumerate() {
+							match line_index == 0 && last > 0 {
+								true => lines.push(format!("{},", member)),
+								false => lines.push(member),
+							}
+						}
+						let comma = if entry + 1 < entries.len() { "," } else { "" };
+						lines.push(format!("{}    }}{}", indent, comma));
+					}
+					lines.push(format!("{}]{}", indent, comma));
+				},
+			}
+		}
+	}
+
+	fn render(&self, depth: usize, lines: &mut Vec<String>) {
+		let indent = "    ".repeat(depth);
+		match self.children.as_slice() {
+			[] => lines.push(format!("{}{};", indent, self.name)),
+			[value] if value.children.is_empty() && !self.is_identifier() => lines.push(format!("{}{} {};", indent, self.name, value.name)),
+			children => {
+				lines.push(format!("{}{} {{", indent, self.name));
+				for child in children {
+					child.render(depth + 1, lines);
+				}
+				lines.push(format!("{}}}", indent));
+			},
+		}
+	}
+}
+
+/// The statements of a configuration in the curly brace format.
+struct Loaded {
+	statements: Vec<String>,
+	/// The hierarchy levels tagged with `replace:`, which `load replace` clears first.
+	replaced: Vec<String>,
+}
+
+/// Parses a configuration in the curly brace format into statements in the `set` format. Comments,
+/// leaf lists like `members [ a b ];` and the `replace:` tag are understood.
+fn parse_curly(lines: &[String]) -> Result<Loaded, String> {
+	let mut loaded = Loaded {
+		statements: Vec::new(),
+		replaced: Vec::new(),
+	};
+	let mut levels: Vec<Vec<String>> = Vec::new();
+	let mut words: Vec<String> = Vec::new();
+	let mut list: Option<Vec<String>> = None;
+	let mut replace = false;
+	let mut in_comment = false;
+
+	for (number, line) in lines.iter().enumerate() {
+		let error = |column: usize, token: &str| format!("terminal:{}:({}) syntax error: {}", number + 1, column, token);
+
+		for (column, token) in tokenize(line) {
+			if in_comment {
+				in_comment = !token.ends_with("*/");
+				continue;
+			}
+
+			match token {
+				token if token.starts_with('#') => break,
+				token if token.starts_with("/*") => in_comment = !token.ends_with("*/"),
+				"replace:" => replace = true,
+				"inactive:" => {},
+				"[" if list.is_none() => list = Some(Vec::new()),
+				"]" if list.is_some() => {},
+				"[" | "]" | "{" | "}" if list.is_some() || token == "]" => return Err(error(column, token)),
+				"{" => {
+					if words.is_empty() || (levels.is_empty() && !TOP_LEVEL_STATEMENTS.contains(&words[0].as_str())) {
+						return Err(error(column, words.first().map_or(token, String::as_str)));
+					}
+					let mut level = levels.last().cloned().unwrap_or_default();
+					level.append(&mut words);
+					if std::mem::take(&mut replace) {
+						loaded.replaced.push(level.join(" "));
+					}
+					levels.push(level);
+				},
+				";" => {
+					if words.is_empty() || (levels.is_empty() && !TOP_LEVEL_STATEMENTS.contains(&words[0].as_str())) {
+						return Err(error(column, words.first().map_or(token, String::as_str)));
+					}
+					let mut statement = levels.last().cloned().unwrap_or_default();
+					statement.append(&mut words);
+					if std::mem::take(&mut replace) {
+						loaded.replaced.push(statement[..statement.len() - 1].join(" "));
+					}
+					match list.take() {
+						Some(values) => {
+							for value in values {
+								loaded.statements.push(format!("{} {}", statement.join(" "), value));
+							}
+						},
+						None => loaded.statements.push(statement.join(" ")),
+					}
+				},
+				"}" => {
+					if !words.is_empty() || levels.pop().is_none() {
+						return Err(error(column, token));
+					}
+				},
+				token => match &mut list {
+					Some(values) => values.push(token.to_string()),
+					None => words.push(token.to_string()),
+				},
+			}
+		}
+	}
+
+	if !levels.is_empty() || !words.is_empty() {
+		return Err(format!("terminal:{}:(0) syntax error: unexpected end of input", lines.len()));
+	}
+	Ok(loaded)
+}
+
+/// Splits a line of the curly brace format into words, quoted strings and the characters `{`, `}`,
+/// `;`, `[` and `]`, with the column each one starts at.
+fn tokenize(line: &str) -> Vec<(usize, &str)> {
+	let mut tokens = Vec::new();
+	let mut start = None;
+	let mut quoted = false;
+
+	for (index, c) in line.char_indices() {
+		match c {
+			'"' => {
+				quoted = !quoted;
+				start.get_or_insert(index);
+			},
+			_ if quoted => {},
+			'{' | '}' | ';' | '[' | ']' => {
+				if let Some(start) = start.take() {
+					tokens.push((start, &line[start..index]));
+				}
+				tokens.push((index, &line[index..index + 1]));
+			},
+			c if c.is_whitespace() => {
+				if let Some(start) = start.take() {
+					tokens.push((start, &line[start..index]));
+				}
+			},
+			_ => {
+				start.get_or_insert(index);
+			},
+		}
+	}
+	if let Some(start) = start {
+		tokens.push((start, &line[start..]));
+	}
+	tokens
+}
+
+/// Checks the candidate configuration before a commit. Only addresses are validated, so commit
+/// errors can be tested by setting an address like `999.0.0.1/24`.
+fn check(candidate: &[String]) -> Option<String> {
+	for statement in candidate {
+		let Some((path, address)) = statement.split_once(" address ") else {
+			continue;
+		};
+
+		let (ip, length) = address.split_once('/').unwrap_or((address, "32"));
+		let valid = ip.split('.').count() == 4
+			&& ip.split('.').all(|octet| octet.parse::<u8>().is_ok())
+			&& length.parse::<u8>().is_ok_and(|length| length <= 32);
+		if !valid {
+			return Some(format!(
+				"[edit {}]\r\n  'address {}'\r\n    invalid ip address or hostname: {}\r\nerror: configuration check-out failed",
+				path, address, address,
+			));
+		}
+	}
+	None
+}
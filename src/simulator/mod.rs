@@ -0,0 +1,285 @@
+//! A fake device to test against without any hardware. The simulator serves a Telnet and an SSH
+//! server on the loopback interface, which emulate the CLI of Cisco IOS or Junos closely enough
+//! for the connections and devices of this crate: the login, `enable`, the configuration modes,
+//! `commit`, a couple of `show` commands and the error messages for invalid input.
+//!
+//! The configuration is shared between all sessions of a simulator, so changes made in one
+//! session can be verified in the next one.
+//!
+//! ```
+//! use rustmiko::devices::cisco::CiscoTelnet;
+//! use rustmiko::devices::generic::connection::ConnectionBuilder;
+//! use rustmiko::devices::generic::device_types::config::Configurable;
+//! use rustmiko::simulator::Simulator;
+//!
+//! let simulator = Simulator::cisco("sw01")
+//!     .credentials("admin", "admin")
+//!     .enable_secret("secret")
+//!     .start()?;
+//!
+//! let builder = ConnectionBuilder::from_addr(simulator.telnet_addr())?
+//!     .username("admin")
+//!     .password("admin");
+//! let mut cisco = CiscoTelnet::connect_with(&builder)?;
+//! cisco.enable("secret")?;
+//! assert!(cisco.send_command("show running-config")?.contains("hostname sw01"));
+//! # Ok::<(), rustmiko::Error>(())
+//! ```
+
+mod cisco;
+mod junos;
+mod ssh;
+mod telnet;
+mod terminal;
+
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use log::debug;
+use tokio::sync::oneshot;
+use crate::error::Result;
+
+/// The CLI the simulator emulates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+	/// Cisco IOS, like on a Catalyst switch.
+	CiscoIos,
+	/// Junos, like on an EX switch or an SRX firewall.
+	Junos,
+}
+
+/// How many lines fit on the screen before the pager stops the output, until the session changes
+/// it with `terminal length` or `set cli screen-length`.
+pub(crate) const DEFAULT_SCREEN_LENGTH: usize = 24;
+
+/// What a command run on the simulated CLI answers with.
+pub(crate) struct Response {
+	pub(crate) output: String,
+	pub(crate) close: bool,
+}
+
+impl Response {
+	pub(crate) fn output<S: Into<String>>(output: S) -> Response {
+		Response {
+			output: output.into(),
+			close: false,
+		}
+	}
+
+	pub(crate) fn close<S: Into<String>>(output: S) -> Response {
+		Response {
+			output: output.into(),
+			close: true,
+		}
+	}
+}
+
+/// The CLI of a simulated device, one per session.
+pub(crate) trait Cli: Send {
+	/// The prompt shown after every command.
+	fn prompt(&self) -> String;
+	/// How many lines fit on the screen before the pager stops the output. 0 turns the pager off.
+	fn screen_length(&self) -> usize;
+	/// Whether the next line is a password, which isn't echoed back.
+	fn hides_input(&self) -> bool {
+		false
+	}
+	/// Run a single line typed by the user.
+	fn execute(&mut self, line: &str) -> Response;
+	/// Ctrl-D typed at the start of a line, which ends input like `load merge terminal`. Returns
+	/// `None` if the CLI ignores it.
+	fn end_of_input(&mut self) -> Option<Response> {
+		None
+	}
+}
+
+/// The configuration of a simulated device, shared by all of its sessions.
+#[derive(Clone)]
+enum DeviceState {
+	Cisco(Arc<Mutex<cisco::CiscoState>>),
+	Junos(Arc<Mutex<junos::JunosState>>),
+}
+
+/// A Simulator describes a fake device. Start it with [`Simulator::start`], which serves it on
+/// random ports of the loopback interface until the returned [`RunningSimulator`] is dropped.
+#[derive(Clone)]
+pub struct Simulator {
+	platform: Platform,
+	hostname: String,
+	username: String,
+	password: String,
+	enable_secret: Option<String>,
+}
+
+impl Simulator {
+	/// Simulate a Cisco IOS device with the given hostname.
+	pub fn cisco<S: Into<String>>(hostname: S) -> Simulator {
+		Self::new(Platform::CiscoIos, hostname)
+	}
+
+	/// Simulate a Junos device with the given hostname.
+	pub fn junos<S: Into<String>>(hostname: S) -> Simulator {
+		Self::new(Platform::Junos, hostname)
+	}
+
+	/// Simulate a device of any platform. The default credentials are `admin` / `admin`.
+	pub fn new<S: Into<String>>(platform: Platform, hostname: S) -> Simulator {
+		Simulator {
+			platform,
+			hostname: hostname.into(),
+			username: "admin".to_string(),
+			password: "admin".to_string(),
+			enable_secret: None,
+		}
+	}
+
+	/// Set the only credentials the login accepts.
+	pub fn credentials<U: Into<String>, P: Into<String>>(mut self, username: U, password: P) -> Self {
+		self.username = username.into();
+		self.password = password.into();
+		self
+	}
+
+	/// Ask for this secret on `enable`. Without one, `enable` doesn't ask for a password. Only
+	/// used by Cisco IOS.
+	pub fn enable_secret<S: Into<String>>(mut self, secret: S) -> Self {
+		self.enable_secret = Some(secret.into());
+		self
+	}
+
+	/// Start serving the simulator on a background thread.
+	pub fn start(self) -> Result<RunningSimulator> {
+		let telnet_listener = TcpListener::bind("127.0.0.1:0")?;
+		let ssh_listener = TcpListener::bind("127.0.0.1:0")?;
+		telnet_listener.set_nonblocking(true)?;
+		ssh_listener.set_nonblocking(true)?;
+
+		let telnet_addr = telnet_listener.local_addr()?;
+		let ssh_addr = ssh_listener.local_addr()?;
+		let host_key = ssh::host_key();
+		let host_key_fingerprint = ssh::fingerprint(&host_key);
+
+		let state = match self.platform {
+			Platform::CiscoIos => DeviceState::Cisco(Arc::new(Mutex::new(cisco::CiscoState::new(&self.hostname, self.enable_secret.clone())))),
+			Platform::Junos => DeviceState::Junos(Arc::new(Mutex::new(junos::JunosState::new(&self.hostname)))),
+		};
+		let device = SimulatedDevice {
+			simulator: self,
+			state,
+		};
+
+		let runtime = tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()?;
+		let (shutdown, stopped) = oneshot::channel();
+
+		thread::spawn(move || {
+			runtime.block_on(async move {
+				let telnet_listener = match tokio::net::TcpListener::from_std(telnet_listener) {
+					Ok(listener) => listener,
+					Err(e) => return debug!("Simulator failed to start: {}", e),
+				};
+				let ssh_listener = match tokio::net::TcpListener::from_std(ssh_listener) {
+					Ok(listener) => listener,
+					Err(e) => return debug!("Simulator failed to start: {}", e),
+				};
+
+				tokio::select! {
+					_ = telnet::serve(telnet_listener, device.clone()) => {},
+					_ = ssh::serve(ssh_listener, device, host_key) => {},
+					_ = stopped => {},
+				}
+				debug!("Simulator stopped");
+			});
+		});
+
+		Ok(RunningSimulator {
+			telnet_addr,
+			ssh_addr,
+			host_key_fingerprint,
+			shutdown: Some(shutdown),
+		})
+	}
+}
+
+/// A simulator with the state shared between its sessions.
+#[derive(Clone)]
+pub(crate) struct SimulatedDevice {
+	simulator: Simulator,
+	state: DeviceState,
+}
+
+impl SimulatedDevice {
+	pub(crate) fn platform(&self) -> Platform {
+		self.simulator.platform
+	}
+
+	pub(crate) fn check_credentials(&self, username: &str, password: &str) -> bool {
+		username == self.simulator.username && password == self.simulator.password
+	}
+
+	/// Start the CLI for a user that just logged in.
+	pub(crate) fn cli(&self, username: &str) -> Box<dyn Cli> {
+		match &self.state {
+			DeviceState::Cisco(state) => Box::new(cisco::CiscoCli::new(state.clone())),
+			DeviceState::Junos(state) => Box::new(junos::JunosCli::new(state.clone(), username)),
+		}
+	}
+}
+
+/// A simulator serving on the loopback interface. It's stopped when this is dropped.
+pub struct RunningSimulator {
+	telnet_addr: SocketAddr,
+	ssh_addr: SocketAddr,
+	host_key_fingerprint: String,
+	shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl RunningSimulator {
+	/// The address of the Telnet server.
+	pub fn telnet_addr(&self) -> SocketAddr {
+		self.telnet_addr
+	}
+
+	/// The address of the SSH server.
+	pub fn ssh_addr(&self) -> SocketAddr {
+		self.ssh_addr
+	}
+
+	/// The SHA256 fingerprint of the host key of the SSH server, which is the same for every
+	/// simulator so it can be pinned.
+	pub fn host_key_fingerprint(&self) -> &str {
+		&self.host_key_fingerprint
+	}
+}
+
+impl Drop for RunningSimulator {
+	fn drop(&mut self) {
+		if let Some(shutdown) = self.shutdown.take() {
+			let _ = shutdown.send(());
+		}
+	}
+}
+
+/// Which keyword an abbreviated word like `conf` stands for.
+pub(crate) enum Keyword<'a> {
+	Found(&'a str),
+	Unknown,
+	Ambiguous,
+}
+
+/// Resolves an abbreviated word against the keywords that are valid at this point, like the CLI
+/// of a real device does.
+pub(crate) fn match_keyword<'a>(word: &str, keywords: &[&'a str]) -> Keyword<'a> {
+	let word = word.to_lowercase();
+	if let Some(keyword) = keywords.iter().find(|keyword| **keyword == word) {
+		return Keyword::Found(keyword);
+	}
+
+	let mut matches = keywords.iter().filter(|keyword| keyword.starts_with(&word));
+	match (matches.next(), matches.next()) {
+		(Some(keyword), None) => Keyword::Found(keyword),
+		(Some(_), Some(_)) => Keyword::Ambiguous,
+		_ => Keyword::Unknown,
+	}
+}
@@ -0,0 +1,122 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use log::debug;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::{HashAlg, PrivateKey};
+use russh::server::{self, Auth, ChannelOpenHandle, Msg, Server as _, Session};
+use russh::{Channel, ChannelId, Pty};
+use tokio::net::TcpListener;
+use crate::simulator::terminal::Terminal;
+use crate::simulator::SimulatedDevice;
+
+/// The seed of the host key. Every simulator uses the same key, so its fingerprint can be pinned.
+const HOST_KEY_SEED: [u8; 32] = *b"rustmiko simulator host key seed";
+
+/// The host key of the simulated device.
+pub(crate) fn host_key() -> PrivateKey {
+	PrivateKey::from(Ed25519Keypair::from_seed(&HOST_KEY_SEED))
+}
+
+/// The fingerprint of a host key in the format of [`host_key_fingerprint`].
+///
+/// [`host_key_fingerprint`]: crate::devices::generic::connection::host_key_fingerprint
+pub(crate) fn fingerprint(host_key: &PrivateKey) -> String {
+	host_key.public_key().fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// Accept SSH connections until the simulator is stopped.
+pub(crate) async fn serve(listener: TcpListener, device: SimulatedDevice, host_key: PrivateKey) {
+	let config = server::Config {
+		keys: vec![host_key],
+		auth_rejection_time: Duration::ZERO,
+		auth_rejection_time_initial: Some(Duration::ZERO),
+		..Default::default()
+	};
+
+	let mut server = SshServer { device };
+	if let Err(e) = server.run_on_socket(Arc::new(config), &listener).await {
+		debug!("Simulator SSH server stopped: {}", e);
+	}
+}
+
+struct SshServer {
+	device: SimulatedDevice,
+}
+
+impl server::Server for SshServer {
+	type Handler = SshSession;
+
+	fn new_client(&mut self, peer: Option<SocketAddr>) -> SshSession {
+		debug!("Simulator accepted an SSH connection from {:?}", peer);
+		SshSession {
+			device: self.device.clone(),
+			username: None,
+			terminal: None,
+		}
+	}
+}
+
+/// A single SSH connection, which serves the CLI on the first shell that is requested.
+struct SshSession {
+	device: SimulatedDevice,
+	username: Option<String>,
+	terminal: Option<Terminal>,
+}
+
+impl server::Handler for SshSession {
+	type Error = russh::Error;
+
+	async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+		if !self.device.check_credentials(user, password) {
+			return Ok(Auth::reject());
+		}
+
+		self.username = Some(user.to_string());
+		Ok(Auth::Accept)
+	}
+
+	async fn channel_open_session(&mut self, _channel: Channel<Msg>, reply: ChannelOpenHandle, _session: &mut Session) -> Result<(), Self::Error> {
+		reply.accept().await;
+		Ok(())
+	}
+
+	async fn pty_request(
+		&mut self,
+		channel: ChannelId,
+		_term: &str,
+		_col_width: u32,
+		_row_height: u32,
+		_pix_width: u32,
+		_pix_height: u32,
+		_modes: &[(Pty, u32)],
+		session: &mut Session,
+	) -> Result<(), Self::Error> {
+		session.channel_success(channel)
+	}
+
+	async fn shell_request(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+		let username = self.username.clone().unwrap_or_default();
+		let (terminal, greeting) = Terminal::logged_in(self.device.clone(), &username);
+		self.terminal = Some(terminal);
+
+		session.channel_success(channel)?;
+		session.data(channel, greeting)
+	}
+
+	async fn data(&mut self, channel: ChannelId, data: &[u8], session: &mut Session) -> Result<(), Self::Error> {
+		let Some(terminal) = &mut self.terminal else {
+			return Ok(());
+		};
+
+		let output = terminal.input(data);
+		if !output.is_empty() {
+			session.data(channel, output)?;
+		}
+		if terminal.is_closed() {
+			session.eof(channel)?;
+			session.close(channel)?;
+		}
+		Ok(())
+	}
+}
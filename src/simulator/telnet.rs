@@ -0,0 +1,41 @@
+use log::debug;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use crate::simulator::terminal::Terminal;
+use crate::simulator::SimulatedDevice;
+
+/// Accept Telnet connections until the simulator is stopped.
+pub(crate) async fn serve(listener: TcpListener, device: SimulatedDevice) {
+	loop {
+		match listener.accept().await {
+			Ok((stream, peer)) => {
+				debug!("Simulator accepted a Telnet connection from {}", peer);
+				tokio::spawn(session(stream, device.clone()));
+			},
+			Err(e) => debug!("Simulator failed to accept a Telnet connection: {}", e),
+		}
+	}
+}
+
+/// Run a single Telnet session. No options are negotiated, the CLI is served as plain text.
+async fn session(mut stream: TcpStream, device: SimulatedDevice) {
+	let (mut terminal, greeting) = Terminal::with_login(device);
+	if stream.write_all(&greeting).await.is_err() {
+		return;
+	}
+
+	let mut buf = [0u8; 1024];
+	while !terminal.is_closed() {
+		let size = match stream.read(&mut buf).await {
+			Ok(0) | Err(_) => break,
+			Ok(size) => size,
+		};
+
+		let output = terminal.input(&buf[..size]);
+		if stream.write_all(&output).await.is_err() {
+			break;
+		}
+	}
+
+	let _ = stream.shutdown().await;
+}
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+use crate::simulator::{Cli, Platform, Response, SimulatedDevice};
+
+/// How often the login may fail before the connection is closed.
+const MAX_LOGIN_ATTEMPTS: usize = 3;
+/// Ctrl-D, which ends the input of a multi-line command.
+const END_OF_INPUT: u8 = 0x04;
+
+enum Stage {
+	Username,
+	Password(String),
+	Cli(Box<dyn Cli>),
+}
+
+/// Output that was stopped by the pager and waits for a key.
+struct Pager {
+	lines: VecDeque<String>,
+	close: bool,
+}
+
+/// A Terminal turns the raw bytes typed by the user into lines for the CLI and answers them like
+/// a terminal on the device would, with the echo, the output and the next prompt.
+pub(crate) struct Terminal {
+	device: SimulatedDevice,
+	stage: Stage,
+	failed_logins: usize,
+	pager: Option<Pager>,
+	line: Vec<u8>,
+	last_was_cr: bool,
+	closed: bool,
+}
+
+impl Terminal {
+	/// A terminal that asks for the username and password first, like on a Telnet line. Returns
+	/// the greeting to send right after connecting.
+	pub(crate) fn with_login(device: SimulatedDevice) -> (Terminal, Vec<u8>) {
+		let greeting = match device.platform() {
+			Platform::CiscoIos => "\r\n\r\nUser Access Verification\r\n\r\nUsername: ".to_string(),
+			Platform::Junos => "\r\n\r\nAmnesiac (ttyp0)\r\n\r\nlogin: ".to_string(),
+		};
+		(Self::new(device, Stage::Username), greeting.into_bytes())
+	}
+
+	/// A terminal for a user that was already authenticated, like on an SSH channel.
+	pub(crate) fn logged_in(device: SimulatedDevice, username: &str) -> (Terminal, Vec<u8>) {
+		let cli = device.cli(username);
+		let greeting = format!("\r\n{}", cli.prompt());
+		(Self::new(device, Stage::Cli(cli)), greeting.into_bytes())
+	}
+
+	fn new(device: SimulatedDevice, stage: Stage) -> Terminal {
+		Terminal {
+			device,
+			stage,
+			failed_logins: 0,
+			pager: None,
+			line: Vec::new(),
+			last_was_cr: false,
+			closed: false,
+		}
+	}
+
+	/// Whether the session ended and the connection should be closed.
+	pub(crate) fn is_closed(&self) -> bool {
+		self.closed
+	}
+
+	/// Feed what the user typed and return the answer. Lines end with `\r`, `\n` or `\r\n`.
+	pub(crate) fn input(&mut self, data: &[u8]) -> Vec<u8> {
+		let mut output = Vec::new();
+
+		for &byte in data {
+			if self.closed {
+				break;
+			}
+
+			let last_was_cr = std::mem::replace(&mut self.last_was_cr, byte == b'\r');
+			if self.pager.is_some() {
+				if !(last_was_cr && matches!(byte, b'\n' | 0)) {
+					output.extend_from_slice(self.key_pressed(byte).as_bytes());
+				}
+				continue;
+			}
+
+			match byte {
+				b'\n' | 0 if last_was_cr => {},
+				b'\r' | b'\n' => {
+					let line = String::from_utf8_lossy(&std::mem::take(&mut self.line)).into_owned();
+					output.extend_from_slice(self.line_entered(&line).as_bytes());
+				},
+				END_OF_INPUT if self.line.is_empty() => output.extend_from_slice(self.end_of_input().as_bytes()),
+				_ => self.line.push(byte),
+			}
+		}
+
+		output
+	}
+
+	fn line_entered(&mut self, line: &str) -> String {
+		match &mut self.stage {
+			Stage::Username => {
+				self.stage = Stage::Password(line.trim().to_string());
+				format!("{}\r\nPassword: ", line)
+			},
+			Stage::Password(username) => {
+				let username = username.clone();
+				if self.device.check_credentials(&username, line) {
+					let cli = self.device.cli(&username);
+					let prompt = cli.prompt();
+					self.stage = Stage::Cli(cli);
+					return format!("\r\n{}", prompt);
+				}
+
+				self.failed_logins += 1;
+				if self.failed_logins >= MAX_LOGIN_ATTEMPTS {
+					self.closed = true;
+				}
+				self.stage = Stage::Username;
+				match self.device.platform() {
+					Platform::CiscoIos => "\r\n% Login invalid\r\n\r\nUsername: ".to_string(),
+					Platform::Junos => "\r\nLogin incorrect\r\nlogin: ".to_string(),
+				}
+			},
+			Stage::Cli(cli) => {
+				let mut output = String::new();
+				if !cli.hides_input() {
+					output.push_str(line);
+				}
+				output.push_str("\r\n");
+
+				let response = cli.execute(line);
+				output.push_str(&self.respond(response));
+				output
+			},
+		}
+	}
+
+	fn end_of_input(&mut self) -> String {
+		let Stage::Cli(cli) = &mut self.stage else {
+			return String::new();
+		};
+		match cli.end_of_input() {
+			Some(response) => self.respond(response),
+			None => String::new(),
+		}
+	}
+
+	/// Shows the output of a command through the pager, followed by the next prompt.
+	fn respond(&mut self, mut response: Response) -> String {
+		if !response.output.is_empty() && !response.output.ends_with('\n') {
+			response.output.push_str("\r\n");
+		}
+
+		let screen_length = match &self.stage {
+			Stage::Cli(cli) => cli.screen_length(),
+			_ => 0,
+		};
+		self.pager = Some(Pager {
+			lines: response.output.split_inclusive('\n').map(String::from).collect(),
+			close: response.close,
+		});
+		match screen_length {
+			0 => self.show_lines(usize::MAX),
+			_ => self.show_lines(screen_length - 1),
+		}
+	}
+
+	/// Shows the next lines of the paged output, followed by the pager if there is more or by the
+	/// prompt if the output is done.
+	fn show_lines(&mut self, count: usize) -> String {
+		let Some(pager) = &mut self.pager else {
+			return String::new();
+		};
+
+		let count = count.max(1).min(pager.lines.len());
+		let mut output: String = pager.lines.drain(..count).collect();
+		if !pager.lines.is_empty() {
+			output.push_str(self.pager_text());
+			return output;
+		}
+
+		let close = pager.close;
+		self.pager = None;
+		output.push_str(&self.finish_output(close));
+		output
+	}
+
+	/// Ends the output of a command with the next prompt, or closes the session.
+	fn finish_output(&mut self, close: bool) -> String {
+		if close {
+			self.closed = true;
+			return String::new();
+		}
+
+		match &self.stage {
+			Stage::Cli(cli) => cli.prompt(),
+			_ => String::new(),
+		}
+	}
+
+	/// Answers a key pressed at the pager: space shows the next page, enter the next line and
+	/// `q` drops the rest of the output.
+	fn key_pressed(&mut self, key: u8) -> String {
+		let mut output = self.pager_erase().to_string();
+		let screen_length = match &self.stage {
+			Stage::Cli(cli) => cli.screen_length(),
+			_ => 0,
+		};
+
+		match key {
+			b'q' | b'Q' => {
+				let close = self.pager.take().is_some_and(|pager| pager.close);
+				output.push_str(&self.finish_output(close));
+			},
+			b'\r' | b'\n' => output.push_str(&self.show_lines(1)),
+			_ => output.push_str(&self.show_lines(screen_length.saturating_sub(1))),
+		}
+		output
+	}
+
+	fn pager_text(&self) -> &'static str {
+		match self.device.platform() {
+			Platform::CiscoIos => " --More-- ",
+			Platform::Junos => "---(more)---",
+		}
+	}
+
+	/// What the device sends to remove the pager from the screen again.
+	fn pager_erase(&self) -> &'static str {
+		match self.device.platform() {
+			Platform::CiscoIos => "\x08\x08\x08\x08\x08\x08\x08\x08\x08\x08          \x08\x08\x08\x08\x08\x08\x08\x08\x08\x08",
+			Platform::Junos => "\r                                        \r",
+		}
+	}
+}
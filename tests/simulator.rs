@@ -0,0 +1,193 @@
+//! End to end tests of the SSH and Telnet connections and the devices against the simulator.
+#![cfg(feature = "simulator")]
+
+use regex::Regex;
+use rustmiko::devices::cisco::{CiscoMode, CiscoSSH, CiscoTelnet};
+use rustmiko::devices::generic::connection::{Connection, ConnectionBuilder, HostKeyPolicy, TelnetConnection};
+use rustmiko::devices::generic::device_types::config::Configurable;
+use rustmiko::devices::juniper::{ConfigFormat, JuniperSSH, JuniperTelnet};
+use rustmiko::simulator::{RunningSimulator, Simulator};
+use rustmiko::Error;
+
+fn cisco() -> RunningSimulator {
+	Simulator::cisco("sw01").enable_secret("secret").start().unwrap()
+}
+
+fn telnet(simulator: &RunningSimulator) -> ConnectionBuilder {
+	ConnectionBuilder::from_addr(simulator.telnet_addr()).unwrap()
+		.username("admin")
+		.password("admin")
+}
+
+fn ssh(simulator: &RunningSimulator) -> ConnectionBuilder {
+	ConnectionBuilder::from_addr(simulator.ssh_addr()).unwrap()
+		.username("admin")
+		.password("admin")
+		.pin_fingerprint(simulator.host_key_fingerprint())
+}
+
+#[test]
+fn cisco_over_telnet() {
+	let simulator = cisco();
+	let mut cisco = CiscoTelnet::connect_with(&telnet(&simulator)).unwrap();
+	assert_eq!(cisco.mode(), &CiscoMode::UserExec);
+
+	cisco.enable("secret").unwrap();
+	assert_eq!(cisco.mode(), &CiscoMode::PrivilegedExec);
+
+	let config = cisco.send_command("show running-config").unwrap();
+	assert!(config.contains("hostname sw01"));
+	assert!(!config.contains("sw01#"));
+}
+
+#[test]
+fn cisco_over_ssh() {
+	let simulator = cisco();
+	let mut cisco = CiscoSSH::connect_with(&ssh(&simulator)).unwrap();
+	cisco.enable("secret").unwrap();
+
+	{
+		let mut config = cisco.enter_config().unwrap();
+		config.execute_raw("interface GigabitEthernet0/1").unwrap();
+		config.execute_raw("description uplink").unwrap();
+		config.finish().unwrap();
+	}
+	assert_eq!(cisco.mode(), &CiscoMode::PrivilegedExec);
+	assert!(cisco.get_running_config().unwrap().contains(" description uplink"));
+}
+
+#[test]
+fn cisco_enable_with_a_wrong_secret_fails() {
+	let simulator = cisco();
+	let mut cisco = CiscoTelnet::connect_with(&telnet(&simulator)).unwrap();
+	assert!(matches!(cisco.enable("wrong"), Err(Error::CommandFailed { .. })));
+	assert_eq!(cisco.mode(), &CiscoMode::UserExec);
+}
+
+#[test]
+fn ssh_with_a_wrong_host_key_fails() {
+	let simulator = cisco();
+	let builder = ConnectionBuilder::from_addr(simulator.ssh_addr()).unwrap()
+		.username("admin")
+		.password("admin")
+		.pin_fingerprint("SHA256:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA");
+	assert!(CiscoSSH::connect_with(&builder).is_err());
+}
+
+#[test]
+fn ssh_accepts_new_host_keys_by_default() {
+	let simulator = cisco();
+	let known_hosts = std::env::temp_dir().join(format!("rustmiko-known-hosts-{}", simulator.ssh_addr().port()));
+	let _ = std::fs::remove_file(&known_hosts);
+	let builder = ConnectionBuilder::from_addr(simulator.ssh_addr()).unwrap()
+		.username("admin")
+		.password("admin")
+		.known_hosts_file(&known_hosts);
+
+	let strict = builder.clone().host_key_policy(HostKeyPolicy::Strict);
+	assert!(matches!(CiscoSSH::connect_with(&strict), Err(Error::HostKeyUnknown { .. })));
+
+	CiscoSSH::connect_with(&builder).unwrap();
+	CiscoSSH::connect_with(&strict).unwrap();
+	std::fs::remove_file(&known_hosts).unwrap();
+}
+
+#[test]
+fn cisco_pager_is_answered_with_a_space() {
+	let simulator = cisco();
+	let mut cisco = CiscoTelnet::connect_with(&telnet(&simulator)).unwrap();
+	cisco.enable("secret").unwrap();
+	// The device turned the pager off, so turn it back on.
+	cisco.execute_raw("terminal length 5").unwrap();
+
+	let config = cisco.send_command("show running-config").unwrap();
+	assert!(config.lines().count() > 5);
+	assert!(!config.contains("--More--"));
+	assert!(config.ends_with("end"));
+}
+
+#[test]
+fn junos_pager_is_answered_with_a_space() {
+	let simulator = Simulator::junos("srx").start().unwrap();
+	// A bare connection doesn't turn the pager off like the devices do.
+	let mut connection = TelnetConnection::connect_with(&telnet(&simulator)).unwrap();
+	let prompt = Regex::new(r"^admin@srx>$").unwrap();
+	connection.find_prompt(&prompt).unwrap();
+
+	let output = connection.send_command("show configuration", &prompt).unwrap();
+	assert!(output.lines().count() > 24);
+	assert!(!output.contains("---(more"));
+}
+
+#[test]
+fn junos_commit_and_rollback() {
+	let simulator = Simulator::junos("srx").start().unwrap();
+	let mut juniper = JuniperSSH::connect_with(&ssh(&simulator)).unwrap();
+
+	{
+		let mut config = juniper.enter_config().unwrap();
+		config.execute_raw("set interfaces ge-0/0/1 description uplink").unwrap();
+		assert_eq!(config.diff().unwrap().added().len(), 1);
+		config.commit().unwrap();
+		config.finish().unwrap();
+	}
+	assert!(juniper.get_config(ConfigFormat::Set).unwrap()
+		.contains("set interfaces ge-0/0/1 description uplink"));
+
+	{
+		let mut config = juniper.enter_config().unwrap();
+		config.rollback(1).unwrap();
+		assert_eq!(config.diff().unwrap().removed().len(), 1);
+		config.commit().unwrap();
+		config.finish().unwrap();
+	}
+	assert!(!juniper.get_config(ConfigFormat::Set).unwrap().contains("uplink"));
+}
+
+#[test]
+fn junos_over_telnet_shares_the_configuration() {
+	let simulator = Simulator::junos("srx").start().unwrap();
+	{
+		let mut juniper = JuniperSSH::connect_with(&ssh(&simulator)).unwrap();
+		let mut config = juniper.enter_config().unwrap();
+		config.execute_raw("set system host-name srx2").unwrap();
+		config.commit().unwrap();
+		config.finish().unwrap();
+		assert_eq!(juniper.base_prompt(), "admin@srx2");
+	}
+
+	let mut juniper = JuniperTelnet::connect_with(&telnet(&simulator)).unwrap();
+	assert_eq!(juniper.base_prompt(), "admin@srx2");
+	assert!(juniper.send_command("show version").unwrap().contains("srx2"));
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+	use rustmiko::devices::cisco::{AsyncCiscoTelnet, CiscoMode};
+	use rustmiko::devices::generic::device_types::async_config::AsyncConfigurable;
+	use rustmiko::devices::juniper::AsyncJuniperSSH;
+	use rustmiko::simulator::Simulator;
+	use super::{ssh, telnet};
+
+	#[tokio::test]
+	async fn cisco_over_telnet() {
+		let simulator = Simulator::cisco("sw01").enable_secret("secret").start().unwrap();
+		let mut cisco = AsyncCiscoTelnet::connect_with_async(&telnet(&simulator)).await.unwrap();
+		cisco.enable_async("secret").await.unwrap();
+		assert_eq!(cisco.mode(), &CiscoMode::PrivilegedExec);
+		assert!(cisco.send_command("show running-config").await.unwrap().contains("hostname sw01"));
+	}
+
+	#[tokio::test]
+	async fn junos_commit_over_ssh() {
+		let simulator = Simulator::junos("srx").start().unwrap();
+		let mut juniper = AsyncJuniperSSH::connect_with_async(&ssh(&simulator)).await.unwrap();
+		{
+			let mut config = juniper.enter_config().await.unwrap();
+			config.execute_raw("set interfaces ge-0/0/1 description uplink").await.unwrap();
+			config.commit().await.unwrap();
+			config.finish().await.unwrap();
+		}
+		assert!(juniper.send_command("show configuration interfaces").await.unwrap().contains("description uplink;"));
+	}
+}